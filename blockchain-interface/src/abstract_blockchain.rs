@@ -3,10 +3,13 @@ use nimiq_block::{Block, MacroBlock};
 use nimiq_collections::BitSet;
 use nimiq_hash::Blake2bHash;
 use nimiq_primitives::{
+    coin::Coin,
     networks::NetworkId,
     policy::Policy,
-    slots_allocation::{Slot, Validators},
+    slots_allocation::{Slot, SlotOwnerDetails, ValidatorSetDiff, Validators},
 };
+use nimiq_serde::Serialize;
+use nimiq_transaction::Transaction;
 use nimiq_vrf::{Rng, VrfEntropy, VrfUseCase};
 
 use crate::{
@@ -75,6 +78,33 @@ pub trait AbstractBlockchain {
     /// Returns the set of validators of the previous epoch.
     fn previous_validators(&self) -> Option<Validators>;
 
+    /// Computes the changes in the validator set between the two given election blocks, i.e.
+    /// which validators were added, removed, retained or rotated their keys.
+    fn validator_set_diff(
+        &self,
+        from_macro: &Blake2bHash,
+        to_macro: &Blake2bHash,
+    ) -> Result<ValidatorSetDiff, BlockchainError> {
+        let from_validators = self
+            .get_block(from_macro, true)?
+            .validators()
+            .ok_or(BlockchainError::NoValidatorsFound)?;
+        let to_validators = self
+            .get_block(to_macro, true)?
+            .validators()
+            .ok_or(BlockchainError::NoValidatorsFound)?;
+
+        Ok(from_validators.diff(&to_validators))
+    }
+
+    /// Returns the minimum fee a transaction with the given serialized size needs to pay under the
+    /// consensus rules. The mempool rejects transactions below this fee, regardless of its own
+    /// rules. As `Policy::MINIMUM_FEE_PER_BYTE` is currently zero, this is always zero.
+    fn min_transaction_fee(&self, tx_size: usize) -> Coin {
+        Coin::try_from(Policy::MINIMUM_FEE_PER_BYTE.saturating_mul(tx_size as u64))
+            .unwrap_or(Coin::MAX)
+    }
+
     /// Checks if the blockchain contains a specific block, by its hash.
     fn contains(&self, hash: &Blake2bHash, include_forks: bool) -> bool;
 
@@ -96,6 +126,37 @@ pub trait AbstractBlockchain {
         direction: Direction,
     ) -> Result<Vec<Block>, BlockchainError>;
 
+    /// Get several blocks like `get_blocks`, but stops early once their accumulated serialized
+    /// size would exceed `max_bytes`. The result is always a prefix of what `get_blocks` returns
+    /// for the same direction, which is empty if the first block alone exceeds the budget.
+    fn get_blocks_budgeted(
+        &self,
+        start_block_hash: &Blake2bHash,
+        max_count: u32,
+        max_bytes: usize,
+        include_body: bool,
+        direction: Direction,
+    ) -> Result<Vec<Block>, BlockchainError> {
+        let mut remaining_bytes = max_bytes;
+        Ok(self
+            .get_blocks(start_block_hash, max_count, include_body, direction)?
+            .into_iter()
+            .take_while(|block| {
+                let size = block.serialized_size();
+                if size > remaining_bytes {
+                    return false;
+                }
+                remaining_bytes -= size;
+                true
+            })
+            .collect())
+    }
+
+    /// Fetches a transaction by its hash, together with the hash of the block that includes it.
+    /// Returns `None` if the transaction is unknown or the blockchain doesn't keep the history of
+    /// transactions.
+    fn get_transaction(&self, hash: &Blake2bHash) -> Option<(Transaction, Blake2bHash)>;
+
     /// Fetches a given chain info, by its hash.
     fn get_chain_info(
         &self,
@@ -103,10 +164,33 @@ pub trait AbstractBlockchain {
         include_body: bool,
     ) -> Result<ChainInfo, BlockchainError>;
 
+    /// Finds the fork point between the main chain and the chain ending in `other_head`, i.e. the
+    /// most recent block on the main chain that is an ancestor of (or equal to) `other_head`.
+    /// Returns its hash and block number, or `None` if `other_head` or one of its ancestors is
+    /// unknown.
+    fn find_common_ancestor(&self, other_head: &Blake2bHash) -> Option<(Blake2bHash, u32)> {
+        let mut hash = other_head.clone();
+        loop {
+            let chain_info = self.get_chain_info(&hash, false).ok()?;
+            if chain_info.on_main_chain {
+                return Some((hash, chain_info.head.block_number()));
+            }
+            hash = chain_info.head.parent_hash().clone();
+        }
+    }
+
     /// Calculates the slot owner (represented as the validator plus the slot number) at a given
     /// block number and offset.
     fn get_proposer_at(&self, block_number: u32, offset: u32) -> Result<Slot, BlockchainError>;
 
+    /// Like `get_proposer_at`, but additionally returns the number of slots owned by the slot
+    /// owner and its current total stake, if the staking contract is available.
+    fn get_slot_owner_details_at(
+        &self,
+        block_number: u32,
+        offset: u32,
+    ) -> Result<SlotOwnerDetails, BlockchainError>;
+
     /// Obtains the slow owner at a given block hash.
     fn get_proposer_of(&self, block_hash: &Blake2bHash) -> Result<Slot, BlockchainError>;
 
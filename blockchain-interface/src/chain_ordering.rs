@@ -1,4 +1,4 @@
-use std::cmp;
+use std::{cmp, sync::Arc};
 
 use nimiq_block::{Block, BlockType};
 use nimiq_hash::Blake2bHash;
@@ -17,6 +17,13 @@ pub enum ChainOrdering {
     // The ordering of this chain is unknown.
     Unknown,
 }
+
+/// Decides between a new block and the current head of the same height if their chains are
+/// otherwise equal according to `ChainOrdering::order_chains`. It is called with the new block
+/// first. If it returns `Ordering::Greater`, the chain of the new block is adopted, otherwise the
+/// new block is stored as a fork.
+pub type ForkTiebreak = Arc<dyn Fn(&Block, &Block) -> cmp::Ordering + Send + Sync>;
+
 /// Implements method to calculate chain ordering.
 impl ChainOrdering {
     /// Given a block and some chain, it returns the ordering of the new chain relative to the given
@@ -24,10 +31,12 @@ impl ChainOrdering {
     /// F and G functions are what the blockchain uses to obtain the chain_info and a block respectively
     /// They are abstracted in such a way, because the regular blockchain uses a DB transaction
     /// whereas the light blockchain does not, but they share all the same logic
+    /// Without a `tiebreak`, chains of equal length that can't be ordered otherwise are `Unknown`.
     pub fn order_chains<B: AbstractBlockchain, F, G>(
         blockchain: &B,
         block: &Block,
         prev_info: &ChainInfo,
+        tiebreak: Option<&ForkTiebreak>,
         get_chain_info: F,
         get_block_at: G,
     ) -> ChainOrdering
@@ -109,6 +118,16 @@ impl ChainOrdering {
                 chain_order = ChainOrdering::Superior;
             }
 
+            // If they are of equal length, let the tiebreak decide, if there is one.
+            if let Some(tiebreak) = tiebreak {
+                if chain_order == ChainOrdering::Unknown
+                    && blockchain.block_number() == block.block_number()
+                    && tiebreak(block, &blockchain.head()) == cmp::Ordering::Greater
+                {
+                    chain_order = ChainOrdering::Superior;
+                }
+            }
+
             log::info!(
                 fork_block_number = current_height - 1,
                 current_block_number = blockchain.block_number(),
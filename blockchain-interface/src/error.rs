@@ -45,6 +45,10 @@ pub enum BlockchainError {
     InvalidEpoch,
     #[error("Accounts diff not found")]
     AccountsDiffNotFound,
+    #[error("Accounts state of the requested block is no longer available")]
+    AccountsStatePruned,
+    #[error("Block is not on the main chain")]
+    BlockNotOnMainChain,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1,19 +1,24 @@
 use std::sync::Arc;
 
-use futures::stream::BoxStream;
+use futures::{future, stream, stream::BoxStream, StreamExt};
 use nimiq_block::{Block, MacroBlock};
 #[cfg(feature = "full")]
 use nimiq_blockchain::Blockchain;
 use nimiq_blockchain_interface::{
     AbstractBlockchain, BlockchainError, BlockchainEvent, ChainInfo, Direction, ForkEvent,
+    ForkTiebreak, PushError, PushResult,
 };
 use nimiq_hash::Blake2bHash;
+use nimiq_keys::Address;
 use nimiq_light_blockchain::LightBlockchain;
 use nimiq_primitives::{
+    coin::Coin,
     networks::NetworkId,
-    slots_allocation::{Slot, Validators},
+    slots_allocation::{Slot, SlotOwnerDetails, ValidatorSetDiff, Validators},
 };
-use parking_lot::{RwLock, RwLockReadGuard};
+use nimiq_transaction::{ExecutedTransaction, Transaction};
+use nimiq_zkp::NanoProof;
+use parking_lot::{RwLock, RwLockReadGuard, RwLockUpgradableReadGuard};
 
 macro_rules! gen_blockchain_match {
     ($self: ident, $t: ident, $f: ident $(, $arg:expr )*) => {
@@ -25,6 +30,62 @@ macro_rules! gen_blockchain_match {
     };
 }
 
+macro_rules! gen_blockchain_write_match {
+    ($self: ident, $t: ident, $f: ident $(, $arg:expr )*) => {
+        match $self {
+            #[cfg(feature = "full")]
+            $t::Full(blockchain) => AbstractBlockchainWrite::$f(blockchain, $( $arg ),*),
+            $t::Light(light_blockchain) => AbstractBlockchainWrite::$f(light_blockchain, $( $arg ),*),
+        }
+    };
+}
+
+/// Defines the methods that modify a blockchain. They consume the lock guard, as the blockchain
+/// only upgrades it to a write lock for the actual write and releases it afterwards.
+pub trait AbstractBlockchainWrite {
+    /// Pushes a block into the chain.
+    fn push(self, block: Block) -> Result<PushResult, PushError>;
+
+    /// Pushes an election block together with a ZK proof of its validity into the chain.
+    fn push_zkp(
+        self,
+        block: Block,
+        proof: NanoProof,
+        trusted_proof: bool,
+    ) -> Result<PushResult, PushError>;
+}
+
+#[cfg(feature = "full")]
+impl<'a> AbstractBlockchainWrite for RwLockUpgradableReadGuard<'a, Blockchain> {
+    fn push(self, block: Block) -> Result<PushResult, PushError> {
+        Blockchain::push(self, block)
+    }
+
+    fn push_zkp(
+        self,
+        block: Block,
+        proof: NanoProof,
+        trusted_proof: bool,
+    ) -> Result<PushResult, PushError> {
+        Blockchain::push_zkp(self, block, proof, trusted_proof)
+    }
+}
+
+impl<'a> AbstractBlockchainWrite for RwLockUpgradableReadGuard<'a, LightBlockchain> {
+    fn push(self, block: Block) -> Result<PushResult, PushError> {
+        LightBlockchain::push(self, block)
+    }
+
+    fn push_zkp(
+        self,
+        block: Block,
+        proof: NanoProof,
+        trusted_proof: bool,
+    ) -> Result<PushResult, PushError> {
+        LightBlockchain::push_zkp(self, block, proof, trusted_proof)
+    }
+}
+
 /// The `BlockchainProxy` is our abstraction over multiple types of blockchains.
 pub enum BlockchainProxy {
     #[cfg(feature = "full")]
@@ -82,6 +143,203 @@ impl BlockchainProxy {
             }
         }
     }
+
+    /// Returns a wrapper/proxy around a blockchain that is locked for writing.
+    /// The `BlockchainWriteProxy` implements `AbstractBlockchainWrite` and allows to push blocks
+    /// without matching on the type of the blockchain.
+    pub fn write(&self) -> BlockchainWriteProxy {
+        match self {
+            #[cfg(feature = "full")]
+            BlockchainProxy::Full(blockchain) => {
+                BlockchainWriteProxy::Full(blockchain.upgradable_read())
+            }
+            BlockchainProxy::Light(light_blockchain) => {
+                BlockchainWriteProxy::Light(light_blockchain.upgradable_read())
+            }
+        }
+    }
+
+    /// Sets the tiebreak that decides between competing chains of equal length. Passing `None`
+    /// restores the default behavior of keeping the first seen chain.
+    pub fn set_tiebreak(&self, tiebreak: Option<ForkTiebreak>) {
+        match self {
+            #[cfg(feature = "full")]
+            BlockchainProxy::Full(blockchain) => blockchain.write().fork_tiebreak = tiebreak,
+            BlockchainProxy::Light(light_blockchain) => {
+                light_blockchain.write().fork_tiebreak = tiebreak
+            }
+        }
+    }
+
+    /// Returns a stream of the blockchain events that concern a block whose body sends to or from
+    /// the given address, including rewards paid to it. Rebranches are emitted if any reverted
+    /// or adopted block touches the address.
+    ///
+    /// Events only carry block hashes, so every event is resolved by looking up its block, which
+    /// takes the blockchain read lock and, for a full blockchain, reads the block body from the
+    /// database. This adds latency to each event compared to `notifier_as_stream`. A light
+    /// blockchain doesn't store block bodies, so its stream doesn't emit any events.
+    pub fn notifier_for_address(&self, address: Address) -> BoxStream<'static, BlockchainEvent> {
+        let blockchain = self.clone();
+        self.read()
+            .notifier_as_stream()
+            .filter(move |event| {
+                let touches_hash = |hash: &Blake2bHash| {
+                    blockchain
+                        .read()
+                        .get_block(hash, true)
+                        .is_ok_and(|block| block_touches(&block, &address))
+                };
+                let touches = match event {
+                    BlockchainEvent::Extended(hash)
+                    | BlockchainEvent::HistoryAdopted(hash)
+                    | BlockchainEvent::Finalized(hash)
+                    | BlockchainEvent::EpochFinalized(hash) => touches_hash(hash),
+                    BlockchainEvent::Rebranched(reverted, adopted) => reverted
+                        .iter()
+                        .chain(adopted)
+                        .any(|(_, block)| block_touches(block, &address)),
+                    BlockchainEvent::Stored(block) => block_touches(block, &address),
+                };
+                future::ready(touches)
+            })
+            .boxed()
+    }
+
+    /// Returns a stream of the main chain blocks following the block with the given hash, in
+    /// ascending order. Blocks are only emitted once they are finalized: the stream first yields
+    /// the blocks up to the current macro head and then waits for further macro blocks to be
+    /// finalized. If the start block is not on the main chain when the block after it is
+    /// emitted, e.g. because it was reorged out, the stream yields
+    /// `BlockchainError::BlockNotOnMainChain` and ends. The stream also ends after any other error.
+    pub fn blocks_from(
+        &self,
+        start_hash: &Blake2bHash,
+        include_body: bool,
+    ) -> BoxStream<'static, Result<Block, BlockchainError>> {
+        // Subscribe before reading the chain, so that no finalization is missed in between.
+        let events = self.read().notifier_as_stream();
+        let start_height = match self.read().get_block(start_hash, false) {
+            Ok(block) => block.block_number(),
+            Err(error) => return stream::once(future::ready(Err(error))).boxed(),
+        };
+
+        let state = BlocksFromState {
+            blockchain: self.clone(),
+            events,
+            start_hash: start_hash.clone(),
+            start_height,
+            next_height: start_height + 1,
+        };
+
+        stream::unfold(Some(state), move |state| async move {
+            let mut state = state?;
+            loop {
+                let result = {
+                    let blockchain = state.blockchain.read();
+                    if state.next_height <= blockchain.macro_head().block_number() {
+                        Some(state.next_block(&blockchain, include_body))
+                    } else {
+                        None
+                    }
+                };
+
+                match result {
+                    Some(Ok(block)) => {
+                        state.next_height += 1;
+                        return Some((Ok(block), Some(state)));
+                    }
+                    Some(Err(error)) => return Some((Err(error), None)),
+                    None => {}
+                }
+
+                // Wait for the next macro block to be finalized.
+                loop {
+                    match state.events.next().await? {
+                        BlockchainEvent::Finalized(_) | BlockchainEvent::EpochFinalized(_) => break,
+                        _ => {}
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Returns whether the body of the given block contains a transaction or reward sent from or to
+/// the given address. Blocks without a body don't touch any address.
+fn block_touches(block: &Block, address: &Address) -> bool {
+    match block {
+        Block::Macro(block) => block.body.as_ref().is_some_and(|body| {
+            body.transactions
+                .iter()
+                .any(|reward| reward.recipient == *address)
+        }),
+        Block::Micro(block) => block.body.as_ref().is_some_and(|body| {
+            body.transactions
+                .iter()
+                .map(ExecutedTransaction::get_raw_transaction)
+                .any(|tx| tx.sender == *address || tx.recipient == *address)
+        }),
+    }
+}
+
+/// The state of the stream returned by `BlockchainProxy::blocks_from`.
+struct BlocksFromState {
+    blockchain: BlockchainProxy,
+    events: BoxStream<'static, BlockchainEvent>,
+    start_hash: Blake2bHash,
+    start_height: u32,
+    next_height: u32,
+}
+
+impl BlocksFromState {
+    fn next_block(
+        &self,
+        blockchain: &BlockchainReadProxy,
+        include_body: bool,
+    ) -> Result<Block, BlockchainError> {
+        // The start block can't be reorged out anymore once the block after it is finalized.
+        if self.next_height == self.start_height + 1
+            && blockchain.get_block_at(self.start_height, false)?.hash() != self.start_hash
+        {
+            return Err(BlockchainError::BlockNotOnMainChain);
+        }
+        blockchain.get_block_at(self.next_height, include_body)
+    }
+}
+
+/// The `BlockchainWriteProxy` implements `AbstractBlockchainWrite` and allows to push blocks into
+/// either of our blockchain types. It holds an upgradable read lock rather than a write lock, since
+/// that is what pushing a block takes: readers are only blocked while the block is written.
+pub enum BlockchainWriteProxy<'a> {
+    #[cfg(feature = "full")]
+    /// Upgradable read locked access to a Full Blockchain
+    Full(RwLockUpgradableReadGuard<'a, Blockchain>),
+    /// Upgradable read locked access to a Light Blockchain
+    Light(RwLockUpgradableReadGuard<'a, LightBlockchain>),
+}
+
+impl<'a> AbstractBlockchainWrite for BlockchainWriteProxy<'a> {
+    fn push(self, block: Block) -> Result<PushResult, PushError> {
+        gen_blockchain_write_match!(self, BlockchainWriteProxy, push, block)
+    }
+
+    fn push_zkp(
+        self,
+        block: Block,
+        proof: NanoProof,
+        trusted_proof: bool,
+    ) -> Result<PushResult, PushError> {
+        gen_blockchain_write_match!(
+            self,
+            BlockchainWriteProxy,
+            push_zkp,
+            block,
+            proof,
+            trusted_proof
+        )
+    }
 }
 
 /// The `BlockchainReadProxy` implements `AbstractBlockchain` and allows to access common blockchain functions.
@@ -127,6 +385,24 @@ impl<'a> AbstractBlockchain for BlockchainReadProxy<'a> {
         gen_blockchain_match!(self, BlockchainReadProxy, previous_validators)
     }
 
+    fn validator_set_diff(
+        &self,
+        from_macro: &Blake2bHash,
+        to_macro: &Blake2bHash,
+    ) -> Result<ValidatorSetDiff, BlockchainError> {
+        gen_blockchain_match!(
+            self,
+            BlockchainReadProxy,
+            validator_set_diff,
+            from_macro,
+            to_macro
+        )
+    }
+
+    fn min_transaction_fee(&self, tx_size: usize) -> Coin {
+        gen_blockchain_match!(self, BlockchainReadProxy, min_transaction_fee, tx_size)
+    }
+
     fn contains(&self, hash: &Blake2bHash, include_forks: bool) -> bool {
         gen_blockchain_match!(self, BlockchainReadProxy, contains, hash, include_forks)
     }
@@ -167,6 +443,10 @@ impl<'a> AbstractBlockchain for BlockchainReadProxy<'a> {
         )
     }
 
+    fn get_transaction(&self, hash: &Blake2bHash) -> Option<(Transaction, Blake2bHash)> {
+        gen_blockchain_match!(self, BlockchainReadProxy, get_transaction, hash)
+    }
+
     fn get_chain_info(
         &self,
         hash: &Blake2bHash,
@@ -181,6 +461,10 @@ impl<'a> AbstractBlockchain for BlockchainReadProxy<'a> {
         )
     }
 
+    fn find_common_ancestor(&self, other_head: &Blake2bHash) -> Option<(Blake2bHash, u32)> {
+        gen_blockchain_match!(self, BlockchainReadProxy, find_common_ancestor, other_head)
+    }
+
     fn get_proposer_at(&self, block_number: u32, offset: u32) -> Result<Slot, BlockchainError> {
         gen_blockchain_match!(
             self,
@@ -191,6 +475,20 @@ impl<'a> AbstractBlockchain for BlockchainReadProxy<'a> {
         )
     }
 
+    fn get_slot_owner_details_at(
+        &self,
+        block_number: u32,
+        offset: u32,
+    ) -> Result<SlotOwnerDetails, BlockchainError> {
+        gen_blockchain_match!(
+            self,
+            BlockchainReadProxy,
+            get_slot_owner_details_at,
+            block_number,
+            offset
+        )
+    }
+
     fn get_proposer_of(&self, block_hash: &Blake2bHash) -> Result<Slot, BlockchainError> {
         gen_blockchain_match!(self, BlockchainReadProxy, get_proposer_of, block_hash)
     }
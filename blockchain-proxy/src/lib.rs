@@ -1,5 +1,7 @@
 //! This crate contains an abstraction over multiple types of blockchains.
 
-pub use blockchain_proxy::{BlockchainProxy, BlockchainReadProxy};
+pub use blockchain_proxy::{
+    AbstractBlockchainWrite, BlockchainProxy, BlockchainReadProxy, BlockchainWriteProxy,
+};
 
 pub(crate) mod blockchain_proxy;
@@ -0,0 +1,289 @@
+use std::{cmp::Ordering, str::FromStr, sync::Arc};
+
+use futures::{FutureExt, StreamExt};
+use nimiq_block::Block;
+use nimiq_blockchain::{BlockProducer, Blockchain, BlockchainConfig};
+use nimiq_blockchain_interface::{
+    AbstractBlockchain, BlockchainError, BlockchainEvent, Direction, PushResult,
+};
+use nimiq_blockchain_proxy::{AbstractBlockchainWrite, BlockchainProxy};
+use nimiq_database::volatile::VolatileDatabase;
+use nimiq_hash::Blake2bHash;
+use nimiq_keys::{Address, KeyPair, PrivateKey};
+use nimiq_primitives::{coin::Coin, networks::NetworkId, policy::Policy};
+use nimiq_serde::Serialize;
+use nimiq_test_log::test;
+use nimiq_test_utils::{
+    block_production::TemporaryBlockProducer,
+    blockchain::{
+        generate_transactions, produce_macro_blocks, push_micro_block, signing_key, voting_key,
+        REWARD_KEY,
+    },
+};
+use nimiq_utils::time::OffsetTime;
+use parking_lot::RwLock;
+
+fn blockchain() -> Arc<RwLock<Blockchain>> {
+    Arc::new(RwLock::new(
+        Blockchain::new(
+            VolatileDatabase::new(20).unwrap(),
+            BlockchainConfig::default(),
+            NetworkId::UnitAlbatross,
+            Arc::new(OffsetTime::new()),
+        )
+        .unwrap(),
+    ))
+}
+
+#[test(tokio::test)]
+async fn it_streams_finalized_blocks_from_a_hash() {
+    let blockchain = blockchain();
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(&producer, &blockchain, 1);
+
+    let proxy = BlockchainProxy::from(&blockchain);
+    let genesis_hash = proxy.read().get_genesis_hash();
+    let mut blocks = proxy.blocks_from(&genesis_hash, false);
+
+    // First, the blocks of the finalized batch are streamed.
+    let mut expected_number = Policy::genesis_block_number() + 1;
+    for _ in 0..Policy::blocks_per_batch() {
+        let block = blocks.next().await.unwrap().unwrap();
+        assert_eq!(block.block_number(), expected_number);
+        expected_number += 1;
+    }
+    assert!(blocks.next().now_or_never().is_none());
+
+    // Then, the blocks of each newly finalized batch follow.
+    produce_macro_blocks(&producer, &blockchain, 1);
+    let block = blocks.next().await.unwrap().unwrap();
+    assert_eq!(block.block_number(), expected_number);
+
+    // An unknown start block is reported as an error.
+    let mut blocks = proxy.blocks_from(&Blake2bHash::default(), false);
+    assert_eq!(
+        blocks.next().await,
+        Some(Err(BlockchainError::BlockNotFound))
+    );
+    assert!(blocks.next().await.is_none());
+}
+
+#[test(tokio::test)]
+async fn it_streams_events_for_an_address() {
+    let blockchain = blockchain();
+    let producer = BlockProducer::new(signing_key(), voting_key());
+
+    let key_pair = KeyPair::from(PrivateKey::from_str(REWARD_KEY).unwrap());
+    let sender = Address::from(&key_pair);
+
+    let proxy = BlockchainProxy::from(&blockchain);
+    let mut sender_events = proxy.notifier_for_address(sender);
+    let mut other_events = proxy.notifier_for_address(Address::from([0xffu8; Address::SIZE]));
+
+    // A block without transactions doesn't touch the sender.
+    push_micro_block(&producer, &blockchain);
+
+    let block_number = blockchain.read().block_number() + 1;
+    let transactions =
+        generate_transactions(&key_pair, block_number, NetworkId::UnitAlbatross, 1, 0);
+    let block = {
+        let blockchain = blockchain.upgradable_read();
+        let block = producer.next_micro_block(
+            &blockchain,
+            blockchain.head().timestamp() + Policy::BLOCK_SEPARATION_TIME,
+            vec![],
+            transactions,
+            vec![],
+            None,
+        );
+        let block = Block::Micro(block);
+        assert_eq!(
+            Blockchain::push(blockchain, block.clone()),
+            Ok(PushResult::Extended)
+        );
+        block
+    };
+
+    assert_eq!(
+        sender_events.next().await,
+        Some(BlockchainEvent::Extended(block.hash()))
+    );
+    assert!(sender_events.next().now_or_never().is_none());
+    assert!(other_events.next().now_or_never().is_none());
+}
+
+#[test(tokio::test)]
+async fn it_gets_a_transaction_by_hash() {
+    let blockchain = blockchain();
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    push_micro_block(&producer, &blockchain);
+
+    let key_pair = KeyPair::from(PrivateKey::from_str(REWARD_KEY).unwrap());
+    let block_number = blockchain.read().block_number() + 1;
+    let transactions =
+        generate_transactions(&key_pair, block_number, NetworkId::UnitAlbatross, 1, 0);
+    let transaction = transactions[0].clone();
+    let block = {
+        let blockchain = blockchain.upgradable_read();
+        let block = producer.next_micro_block(
+            &blockchain,
+            blockchain.head().timestamp() + Policy::BLOCK_SEPARATION_TIME,
+            vec![],
+            transactions,
+            vec![],
+            None,
+        );
+        let block = Block::Micro(block);
+        assert_eq!(
+            Blockchain::push(blockchain, block.clone()),
+            Ok(PushResult::Extended)
+        );
+        block
+    };
+
+    let proxy = BlockchainProxy::from(&blockchain);
+    assert_eq!(
+        proxy.read().get_transaction(&transaction.hash()),
+        Some((transaction, block.hash()))
+    );
+    assert_eq!(proxy.read().get_transaction(&Blake2bHash::default()), None);
+}
+
+#[test(tokio::test)]
+async fn it_pushes_blocks_through_the_write_proxy() {
+    let blockchain = blockchain();
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    let proxy = BlockchainProxy::from(&blockchain);
+
+    let block = {
+        let blockchain = blockchain.read();
+        Block::Micro(producer.next_micro_block(
+            &blockchain,
+            blockchain.head().timestamp() + Policy::BLOCK_SEPARATION_TIME,
+            vec![],
+            vec![],
+            vec![],
+            None,
+        ))
+    };
+    assert_eq!(proxy.write().push(block.clone()), Ok(PushResult::Extended));
+    assert_eq!(proxy.read().head_hash(), block.hash());
+
+    // Pushing the same block again is reported as known.
+    assert_eq!(proxy.write().push(block), Ok(PushResult::Known));
+}
+
+#[test(tokio::test)]
+async fn it_gets_blocks_within_a_byte_budget() {
+    let blockchain = blockchain();
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    for _ in 0..5 {
+        push_micro_block(&producer, &blockchain);
+    }
+
+    let proxy = BlockchainProxy::from(&blockchain);
+    let genesis_hash = proxy.read().get_genesis_hash();
+    let blocks = proxy
+        .read()
+        .get_blocks(&genesis_hash, 5, true, Direction::Forward)
+        .unwrap();
+    assert_eq!(blocks.len(), 5);
+
+    // The byte budget cuts off before the count does.
+    let max_bytes = blocks[0].serialized_size() + blocks[1].serialized_size();
+    let budgeted = proxy
+        .read()
+        .get_blocks_budgeted(&genesis_hash, 5, max_bytes, true, Direction::Forward)
+        .unwrap();
+    assert_eq!(budgeted, blocks[..2]);
+
+    // The same holds in the backward direction.
+    let head_hash = proxy.read().head_hash();
+    let blocks = proxy
+        .read()
+        .get_blocks(&head_hash, 5, true, Direction::Backward)
+        .unwrap();
+    let max_bytes = blocks[0].serialized_size() + blocks[1].serialized_size() + 1;
+    let budgeted = proxy
+        .read()
+        .get_blocks_budgeted(&head_hash, 5, max_bytes, true, Direction::Backward)
+        .unwrap();
+    assert_eq!(budgeted, blocks[..2]);
+
+    // A budget that doesn't fit the first block yields no blocks.
+    let budgeted = proxy
+        .read()
+        .get_blocks_budgeted(&genesis_hash, 5, 0, true, Direction::Forward)
+        .unwrap();
+    assert!(budgeted.is_empty());
+}
+
+#[test(tokio::test)]
+async fn it_sets_the_fork_tiebreak() {
+    let temp_producer1 = TemporaryBlockProducer::new();
+    let temp_producer2 = TemporaryBlockProducer::new();
+    let proxy = BlockchainProxy::from(&temp_producer1.blockchain);
+
+    // [0] - [0]
+    //    |- [0]
+    //    |- [0]
+    //    \- [0]
+    let block = temp_producer1.next_block(vec![], false);
+    temp_producer2.push(block).unwrap();
+
+    temp_producer1.next_block(vec![0x1], false);
+    let fork1 = temp_producer2.next_block_no_push(vec![0x2], false);
+    let fork2 = temp_producer2.next_block_no_push(vec![0x3], false);
+    let fork3 = temp_producer2.next_block_no_push(vec![0x4], false);
+
+    // Without a tiebreak, the first seen chain is kept.
+    assert_eq!(temp_producer1.push(fork1), Ok(PushResult::Forked));
+
+    // The tiebreak adopts the chain of the new block.
+    proxy.set_tiebreak(Some(Arc::new(|_: &Block, _: &Block| Ordering::Greater)));
+    assert_eq!(
+        temp_producer1.push(fork2.clone()),
+        Ok(PushResult::Rebranched)
+    );
+    assert_eq!(proxy.read().head_hash(), fork2.hash());
+
+    // Removing the tiebreak restores the default.
+    proxy.set_tiebreak(None);
+    assert_eq!(temp_producer1.push(fork3), Ok(PushResult::Forked));
+    assert_eq!(proxy.read().head_hash(), fork2.hash());
+}
+
+#[test]
+fn it_computes_the_minimum_transaction_fee() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let proxy = BlockchainProxy::from(&temp_producer.blockchain);
+
+    // The consensus rules don't require any fee.
+    assert_eq!(proxy.read().min_transaction_fee(0), Coin::ZERO);
+    assert_eq!(proxy.read().min_transaction_fee(1000), Coin::ZERO);
+    assert_eq!(
+        temp_producer.blockchain.read().min_transaction_fee(1000),
+        Coin::ZERO
+    );
+}
+
+#[test]
+fn it_returns_slot_owner_details() {
+    let temp_producer = TemporaryBlockProducer::new();
+    temp_producer.next_block(vec![], false);
+    let proxy = BlockchainProxy::from(&temp_producer.blockchain);
+
+    let block_number = proxy.read().block_number() + 1;
+    let slot = proxy
+        .read()
+        .get_proposer_at(block_number, block_number)
+        .unwrap();
+    let details = proxy
+        .read()
+        .get_slot_owner_details_at(block_number, block_number)
+        .unwrap();
+
+    assert_eq!(details.slot.validator.address, slot.validator.address);
+    assert_eq!(details.num_slots, slot.validator.num_slots());
+    assert!(details.current_total_stake.is_some());
+}
@@ -5,7 +5,8 @@ use nimiq_blockchain_interface::{
 };
 use nimiq_genesis::NetworkId;
 use nimiq_hash::Blake2bHash;
-use nimiq_primitives::slots_allocation::{Slot, Validators};
+use nimiq_primitives::slots_allocation::{Slot, SlotOwnerDetails, Validators};
+use nimiq_transaction::Transaction;
 use tokio_stream::wrappers::BroadcastStream;
 
 use crate::Blockchain;
@@ -84,6 +85,10 @@ impl AbstractBlockchain for Blockchain {
         self.get_blocks(start_block_hash, count, include_body, direction, None)
     }
 
+    fn get_transaction(&self, hash: &Blake2bHash) -> Option<(Transaction, Blake2bHash)> {
+        self.get_transaction(hash, None)
+    }
+
     fn get_chain_info(
         &self,
         hash: &Blake2bHash,
@@ -96,6 +101,14 @@ impl AbstractBlockchain for Blockchain {
         self.get_proposer_at(block_number, offset, None)
     }
 
+    fn get_slot_owner_details_at(
+        &self,
+        block_number: u32,
+        offset: u32,
+    ) -> Result<SlotOwnerDetails, BlockchainError> {
+        self.get_slot_owner_details_at(block_number, offset, None)
+    }
+
     fn get_proposer_of(&self, block_hash: &Blake2bHash) -> Result<Slot, BlockchainError> {
         self.get_proposer_of(block_hash, None)
     }
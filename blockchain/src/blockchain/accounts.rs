@@ -2,8 +2,9 @@ use nimiq_account::{
     Account, Accounts, BlockLogger, BlockState, RevertInfo, TransactionOperationReceipt,
 };
 use nimiq_block::{Block, BlockError, SkipBlockInfo};
-use nimiq_blockchain_interface::PushError;
+use nimiq_blockchain_interface::{BlockchainError, PushError};
 use nimiq_database::{traits::Database, TransactionProxy};
+use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
 use nimiq_primitives::{
     key_nibbles::KeyNibbles,
@@ -239,6 +240,101 @@ impl Blockchain {
         Ok(total_size)
     }
 
+    /// Returns the account at the given address as of the state right after the block with the
+    /// given hash was applied. This also works for retained blocks that are not on the main chain,
+    /// e.g. blocks that were reorged out. The accounts tree only keeps the current state, so this
+    /// can't be answered by `Accounts` itself. Instead, the stored accounts diffs are walked back
+    /// from the given block, which only needs a read transaction. Returns
+    /// `BlockchainError::AccountsStatePruned` if a needed diff is not available, e.g. because the
+    /// block was pushed while the accounts tree was incomplete, and `Ok(None)` if the current
+    /// state is needed but the account is in the missing part of an incomplete tree.
+    pub fn get_account_at_block(
+        &self,
+        address: &Address,
+        block_hash: &Blake2bHash,
+        txn_option: Option<&TransactionProxy>,
+    ) -> Result<Option<Account>, BlockchainError> {
+        let read_txn: TransactionProxy;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = self.read_transaction();
+                &read_txn
+            }
+        };
+        let key = KeyNibbles::from(address);
+
+        // Walk back to the main chain. The latest change on the way determines the account.
+        let mut hash = block_hash.clone();
+        let mut chain_info = self.chain_store.get_chain_info(&hash, false, Some(txn))?;
+        while !chain_info.on_main_chain {
+            if let Some(value) = self.get_accounts_diff_value(&hash, &key, txn)? {
+                return Self::account_from_diff_value(value).map(Some);
+            }
+            hash = chain_info.head.parent_hash().clone();
+            chain_info = self.chain_store.get_chain_info(&hash, false, Some(txn))?;
+        }
+
+        // If no later main chain block changed the account, it is still the current one.
+        let ancestor_number = chain_info.head.block_number();
+        let head_number = self.state.main_chain.head.block_number();
+        let mut changed_later = false;
+        for number in ancestor_number + 1..=head_number {
+            let hash = self
+                .chain_store
+                .get_block_at(number, false, Some(txn))?
+                .hash();
+            if self.get_accounts_diff_value(&hash, &key, txn)?.is_some() {
+                changed_later = true;
+                break;
+            }
+        }
+        if !changed_later {
+            return Ok(self.state.accounts.get(address, Some(txn)).ok());
+        }
+
+        // Otherwise, the latest change up to the main chain ancestor determines the account.
+        let mut number = ancestor_number;
+        loop {
+            let hash = self
+                .chain_store
+                .get_block_at(number, false, Some(txn))?
+                .hash();
+            if let Some(value) = self.get_accounts_diff_value(&hash, &key, txn)? {
+                return Self::account_from_diff_value(value).map(Some);
+            }
+            number = number
+                .checked_sub(1)
+                .ok_or(BlockchainError::AccountsStatePruned)?;
+        }
+    }
+
+    /// Returns the value the accounts diff of the given block sets for the given key, or `None`
+    /// if the block didn't change the key.
+    fn get_accounts_diff_value(
+        &self,
+        block_hash: &Blake2bHash,
+        key: &KeyNibbles,
+        txn: &TransactionProxy,
+    ) -> Result<Option<Option<Vec<u8>>>, BlockchainError> {
+        let diff = self
+            .chain_store
+            .get_accounts_diff(block_hash, Some(txn))
+            .map_err(|error| match error {
+                BlockchainError::AccountsDiffNotFound => BlockchainError::AccountsStatePruned,
+                error => error,
+            })?;
+        Ok(diff.0.get(key).cloned())
+    }
+
+    fn account_from_diff_value(value: Option<Vec<u8>>) -> Result<Account, BlockchainError> {
+        match value {
+            Some(value) => Account::deserialize_from_vec(&value)
+                .map_err(|_| BlockchainError::InconsistentState),
+            None => Ok(Account::default()),
+        }
+    }
+
     /// Produces a Merkle proof of the inclusion of the given keys in the
     /// Merkle Radix Trie.
     pub fn get_accounts_proof(&self, keys: Vec<&KeyNibbles>) -> Result<TrieProof, IncompleteTrie> {
@@ -247,6 +343,20 @@ impl Blockchain {
         self.state.accounts.get_proof(Some(&txn), keys)
     }
 
+    /// Returns the subtree commitments of the accounts tree for the given prefixes, see
+    /// `Accounts::subtree_commitment`.
+    pub fn get_accounts_subtree_commitments(
+        &self,
+        prefixes: &[KeyNibbles],
+    ) -> Result<Vec<Option<Blake2bHash>>, IncompleteTrie> {
+        let txn = self.env.read_transaction();
+
+        prefixes
+            .iter()
+            .map(|prefix| self.state.accounts.subtree_commitment(prefix, Some(&txn)))
+            .collect()
+    }
+
     /// Gets an accounts chunk given a start key and a limit
     pub fn get_accounts_chunk(
         &self,
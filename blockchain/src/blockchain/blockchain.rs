@@ -2,7 +2,9 @@ use std::sync::Arc;
 
 use nimiq_account::{Accounts, BlockLog};
 use nimiq_block::Block;
-use nimiq_blockchain_interface::{BlockchainError, BlockchainEvent, ChainInfo, ForkEvent};
+use nimiq_blockchain_interface::{
+    BlockchainError, BlockchainEvent, ChainInfo, ForkEvent, ForkTiebreak,
+};
 use nimiq_database::{
     traits::{Database, WriteTransaction},
     DatabaseProxy, TransactionProxy, WriteTransactionProxy,
@@ -39,6 +41,8 @@ pub struct Blockchain {
     pub notifier: BroadcastSender<BlockchainEvent>,
     /// The fork notifier processes fork events.
     pub fork_notifier: BroadcastSender<ForkEvent>,
+    /// Decides between competing chains of equal length. If `None`, the first seen chain is kept.
+    pub fork_tiebreak: Option<ForkTiebreak>,
     /// The log notifier processes all events regarding accounts changes.
     pub log_notifier: BroadcastSender<BlockLog>,
     /// The chain store is a database containing all of the chain infos, blocks and receipts.
@@ -69,6 +73,8 @@ pub struct BlockchainConfig {
     /// Maximum number of epochs (other than the current one) that the ChainStore will store fully.
     /// Epochs older than this number will be pruned.
     pub max_epochs_stored: u32,
+    /// Number of accounts trie nodes to cache across read transactions. `None` disables the cache.
+    pub accounts_node_cache_size: Option<usize>,
 }
 
 impl Default for BlockchainConfig {
@@ -76,6 +82,7 @@ impl Default for BlockchainConfig {
         Self {
             keep_history: true,
             max_epochs_stored: Policy::MIN_EPOCHS_STORED,
+            accounts_node_cache_size: None,
         }
     }
 }
@@ -192,7 +199,11 @@ impl Blockchain {
             .map_err(|_| BlockchainError::FailedLoadingMainChain)?;
 
         // Check that chain/accounts state is consistent.
-        let accounts = Accounts::new(env.clone());
+        let accounts = match config.accounts_node_cache_size {
+            Some(cache_size) => Accounts::with_node_cache(env.clone(), cache_size),
+            None => Accounts::new(env.clone()),
+        }
+        .with_network_id(network_id);
 
         // Verify accounts hash if the tree is complete or changes only happened in the complete part.
         if let Some(accounts_hash) = accounts.get_root_hash(None) {
@@ -272,6 +283,7 @@ impl Blockchain {
             time,
             notifier: tx,
             fork_notifier: tx_fork,
+            fork_tiebreak: None,
             log_notifier: tx_log,
             chain_store,
             history_store: Box::new(history_store) as Box<dyn HistoryInterface + Sync + Send>,
@@ -319,7 +331,11 @@ impl Blockchain {
         let main_chain = ChainInfo::new(genesis_block, true);
 
         // Initialize accounts.
-        let accounts = Accounts::new(env.clone());
+        let accounts = match config.accounts_node_cache_size {
+            Some(cache_size) => Accounts::with_node_cache(env.clone(), cache_size),
+            None => Accounts::new(env.clone()),
+        }
+        .with_network_id(network_id);
         let mut txn = env.write_transaction();
         accounts.init(&mut (&mut txn).into(), genesis_accounts);
 
@@ -339,6 +355,7 @@ impl Blockchain {
             time,
             notifier: tx,
             fork_notifier: tx_fork,
+            fork_tiebreak: None,
             log_notifier: tx_log,
             chain_store,
             history_store: Box::new(history_store) as Box<dyn HistoryInterface + Sync + Send>,
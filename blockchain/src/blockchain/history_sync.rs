@@ -175,7 +175,8 @@ impl Blockchain {
         // We might already know the given epoch partially.
         // Revert our chain to a common ancestor state in case we have adopted a different history.
         // Also skip over any transactions that we already know.
-        let first_new_hist_tx = this.revert_to_common_state(&block, history, &mut txn);
+        let (first_new_hist_tx, num_reverted_blocks) =
+            this.revert_to_common_state(&block, history, &mut txn);
 
         // Separate the historic transactions by block number and type.
         // We know it comes sorted because we already checked it against the history root and
@@ -402,6 +403,12 @@ impl Blockchain {
 
         // Give up database transactions and push lock before creating notifications.
         txn.commit();
+        this.state
+            .accounts
+            .count_reverted_blocks(num_reverted_blocks as u64);
+        this.state
+            .accounts
+            .count_committed_blocks(block_numbers.len() as u64);
 
         // Update the blockchain state.
         let mut this = RwLockUpgradableReadGuard::upgrade(this);
@@ -459,13 +466,14 @@ impl Blockchain {
         block: &Block,
         history: &[HistoricTransaction],
         txn: &mut WriteTransactionProxy,
-    ) -> usize {
+    ) -> (usize, u32) {
         // Find the index of the first historic transaction in the current batch.
         let last_macro_block = Policy::last_macro_block(self.block_number());
         let mut first_new_hist_tx = history
             .iter()
             .position(|hist_tx| hist_tx.block_number > last_macro_block)
             .unwrap_or(history.len());
+        let mut num_reverted_blocks = 0;
 
         // Check if our adopted non-final history matches the given history.
         // Revert any blocks that don't match.
@@ -504,6 +512,7 @@ impl Blockchain {
                 let num_blocks_to_revert = self.block_number() - diverging_block + 1;
                 self.revert_blocks(num_blocks_to_revert, txn)
                     .expect("Failed to revert chain");
+                num_reverted_blocks = num_blocks_to_revert;
 
                 // TODO We could incorporate this into the parallel iteration loop above.
                 first_new_hist_tx += history
@@ -524,10 +533,11 @@ impl Blockchain {
                 let num_blocks_to_revert = self.block_number() - first_block_number + 1;
                 self.revert_blocks(num_blocks_to_revert, txn)
                     .expect("Failed to revert chain");
+                num_reverted_blocks = num_blocks_to_revert;
             }
         };
 
-        first_new_hist_tx
+        (first_new_hist_tx, num_reverted_blocks)
     }
 
     /// Reverts a given number of micro or skip blocks from the blockchain.
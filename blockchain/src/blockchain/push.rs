@@ -112,6 +112,7 @@ impl Blockchain {
             this.deref(),
             &block,
             &prev_info,
+            this.fork_tiebreak.as_ref(),
             |hash| this.get_chain_info(hash, false, Some(&read_txn)),
             |height| this.get_block_at(height, false, Some(&read_txn)),
         );
@@ -336,6 +337,7 @@ impl Blockchain {
         }
 
         txn.commit();
+        this.state.accounts.count_committed_blocks(1);
 
         if let Block::Macro(ref macro_block) = chain_info.head {
             this.state.macro_info = chain_info.clone();
@@ -450,6 +452,12 @@ impl Blockchain {
         let new_head_info = &fork_chain[0].1;
         this.chain_store.set_head(&mut write_txn, new_head_hash);
         write_txn.commit();
+        this.state
+            .accounts
+            .count_reverted_blocks(revert_chain.len() as u64);
+        this.state
+            .accounts
+            .count_committed_blocks(fork_chain.len() as u64);
 
         if let Block::Macro(ref macro_block) = new_head_info.head {
             this.state.macro_info = new_head_info.clone();
@@ -2,7 +2,7 @@ use nimiq_blockchain_interface::{AbstractBlockchain, BlockchainError};
 use nimiq_database::TransactionProxy;
 use nimiq_primitives::{
     policy::Policy,
-    slots_allocation::{Slot, Validators},
+    slots_allocation::{Slot, SlotOwnerDetails, Validators},
 };
 use nimiq_vrf::{VrfEntropy, VrfSeed};
 
@@ -45,10 +45,10 @@ impl Blockchain {
 
     /// Calculates the next validators from a given seed.
     pub fn next_validators(&self, seed: &VrfSeed) -> Validators {
-        let staking_contract = self.get_staking_contract();
-        let data_store = self.get_staking_contract_store();
-        let txn = self.read_transaction();
-        staking_contract.select_validators(&data_store.read(&txn), seed)
+        self.state
+            .accounts
+            .compute_validators(seed, None)
+            .expect("We should always have the staking contract.")
     }
 
     pub fn get_proposer(
@@ -93,4 +93,40 @@ impl Blockchain {
             validator: validator.clone(),
         })
     }
+
+    /// Like `get_proposer_at`, but additionally returns the number of slots owned by the proposer
+    /// and its current total stake. Note that the stake is read from the current staking
+    /// contract, not from the one at the given block number.
+    pub fn get_slot_owner_details_at(
+        &self,
+        block_number: u32,
+        offset: u32,
+        txn_option: Option<&TransactionProxy>,
+    ) -> Result<SlotOwnerDetails, BlockchainError> {
+        let slot = self.get_proposer_at(block_number, offset, txn_option)?;
+        let num_slots = slot.validator.num_slots();
+
+        let read_txn: TransactionProxy;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = self.read_transaction();
+                &read_txn
+            }
+        };
+
+        let current_total_stake = self
+            .get_staking_contract_if_complete(Some(txn))
+            .and_then(|staking_contract| {
+                let data_store = self.get_staking_contract_store();
+                staking_contract.get_validator(&data_store.read(txn), &slot.validator.address)
+            })
+            .map(|validator| validator.total_stake);
+
+        Ok(SlotOwnerDetails {
+            slot,
+            num_slots,
+            current_total_stake,
+        })
+    }
 }
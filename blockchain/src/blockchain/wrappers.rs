@@ -11,7 +11,7 @@ use nimiq_keys::Address;
 use nimiq_primitives::{
     account::AccountError, key_nibbles::KeyNibbles, policy::Policy, slots_allocation::Slot,
 };
-use nimiq_transaction::Transaction;
+use nimiq_transaction::{historic_transaction::HistoricTransactionData, Transaction};
 
 #[cfg(feature = "metrics")]
 use crate::chain_metrics::BlockchainMetrics;
@@ -218,6 +218,31 @@ impl Blockchain {
         false
     }
 
+    /// Looks up a transaction by its hash in the history store and returns it together with the
+    /// hash of the block that includes it. Only basic transactions are returned, inherents can't
+    /// be looked up this way. If the same transaction was included more than once, the most
+    /// recent inclusion is returned.
+    pub fn get_transaction(
+        &self,
+        tx_hash: &Blake2bHash,
+        txn_option: Option<&DBTransaction>,
+    ) -> Option<(Transaction, Blake2bHash)> {
+        let (transaction, block_number) = self
+            .history_store
+            .get_hist_tx_by_hash(tx_hash, txn_option)
+            .into_iter()
+            .filter_map(|hist_tx| match hist_tx.data {
+                HistoricTransactionData::Basic(tx) => {
+                    Some((tx.get_raw_transaction().clone(), hist_tx.block_number))
+                }
+                _ => None,
+            })
+            .max_by_key(|(_, block_number)| *block_number)?;
+
+        let block = self.get_block_at(block_number, false, txn_option).ok()?;
+        Some((transaction, block.hash()))
+    }
+
     /// Checks if we have seen some transaction with this hash inside the validity window. This is
     /// used to prevent replay attacks.
     pub fn contains_tx_in_validity_window(
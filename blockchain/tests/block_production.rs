@@ -2,7 +2,7 @@ use std::{convert::TryInto, sync::Arc};
 
 use nimiq_block::{Block, ForkProof, MicroJustification};
 use nimiq_blockchain::{BlockProducer, Blockchain, BlockchainConfig};
-use nimiq_blockchain_interface::{AbstractBlockchain, PushResult};
+use nimiq_blockchain_interface::{AbstractBlockchain, BlockchainError, PushResult};
 use nimiq_bls::KeyPair as BlsKeyPair;
 use nimiq_database::{mdbx::MdbxDatabase, traits::WriteTransaction, volatile::VolatileDatabase};
 use nimiq_genesis::NetworkId;
@@ -1381,6 +1381,77 @@ fn it_can_revert_basic_and_create_contracts_txns() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn it_computes_the_validator_set_diff_between_election_blocks() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let blockchain = &temp_producer.blockchain;
+    let genesis_hash = blockchain.read().election_head_hash();
+
+    // Add a new validator.
+    let mut rng = test_rng(false);
+    let key_pair = ed25519_key_pair(ACCOUNT_SECRET_KEY);
+    let cold_key_pair = SchnorrKeyPair::generate(&mut rng);
+    let voting_key_pair = BlsKeyPair::generate(&mut rng);
+    let create_tx = TransactionBuilder::new_create_validator(
+        &key_pair,
+        &cold_key_pair,
+        SchnorrPublicKey::default(),
+        &voting_key_pair,
+        Address::from([0u8; 20]),
+        None,
+        Coin::ZERO,
+        blockchain.read().block_number() + 1,
+        NetworkId::UnitAlbatross,
+    )
+    .unwrap();
+    let micro_block = temp_producer.next_block_with_txs(vec![], false, vec![create_tx]);
+
+    // Produce batches until the next election block elects the new validator.
+    while !Policy::is_election_block_at(blockchain.read().block_number()) {
+        produce_macro_blocks(&temp_producer.producer, blockchain, 1);
+    }
+    let election_hash = blockchain.read().election_head_hash();
+
+    let new_validator = Address::from(&cold_key_pair.public);
+    let diff = blockchain
+        .read()
+        .validator_set_diff(&genesis_hash, &election_hash)
+        .unwrap();
+    assert_eq!(
+        diff.added
+            .iter()
+            .map(|validator| validator.address.clone())
+            .collect::<Vec<_>>(),
+        vec![new_validator.clone()]
+    );
+    assert_eq!(
+        diff.retained
+            .iter()
+            .map(|validator| validator.address.clone())
+            .collect::<Vec<_>>(),
+        vec![validator_address()]
+    );
+    assert!(diff.removed.is_empty());
+    assert!(diff.rotated.is_empty());
+
+    // In the other direction, the new validator is removed.
+    let diff = blockchain
+        .read()
+        .validator_set_diff(&election_hash, &genesis_hash)
+        .unwrap();
+    assert!(diff.added.is_empty());
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(diff.removed[0].address, new_validator);
+
+    // Only election blocks have a validator set.
+    assert_eq!(
+        blockchain
+            .read()
+            .validator_set_diff(&genesis_hash, &micro_block.hash()),
+        Err(BlockchainError::NoValidatorsFound)
+    );
+}
+
 fn ed25519_key_pair(secret_key: &str) -> SchnorrKeyPair {
     let priv_key: SchnorrPrivateKey =
         Deserialize::deserialize_from_vec(&hex::decode(secret_key).unwrap()[..]).unwrap();
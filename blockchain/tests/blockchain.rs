@@ -2,9 +2,12 @@ use std::sync::Arc;
 
 use nimiq_block::{Block, BlockError};
 use nimiq_blockchain::Blockchain;
-use nimiq_blockchain_interface::{AbstractBlockchain, PushError, PushResult};
-use nimiq_hash::Hash;
-use nimiq_primitives::{policy::Policy, trie::trie_diff::TrieDiff};
+use nimiq_blockchain_interface::{AbstractBlockchain, BlockchainError, PushError, PushResult};
+use nimiq_genesis::NetworkId;
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_keys::{Address, KeyPair, PrivateKey};
+use nimiq_primitives::{coin::Coin, policy::Policy, trie::trie_diff::TrieDiff};
+use nimiq_serde::Deserialize;
 use nimiq_tendermint::ProposalMessage;
 use nimiq_test_log::test;
 use nimiq_test_utils::{
@@ -12,6 +15,17 @@ use nimiq_test_utils::{
     blockchain::produce_macro_blocks,
     test_custom_block::{finalize_macro_block, next_macro_block_proposal},
 };
+use nimiq_transaction_builder::TransactionBuilder;
+
+fn key_pair_with_funds() -> KeyPair {
+    let priv_key: PrivateKey =
+        Deserialize::deserialize_from_vec(
+            &hex::decode("6c9320ac201caf1f8eaa5b05f5d67a9e77826f3f6be266a0ecccc20416dc6587")
+                .unwrap()[..],
+        )
+        .unwrap();
+    priv_key.into()
+}
 
 #[test]
 fn prune_epoch_micro_blocks() {
@@ -166,3 +180,94 @@ fn can_detect_invalid_punished_set() {
         Err(PushError::InvalidBlock(BlockError::InvalidValidators))
     );
 }
+
+#[test]
+fn it_returns_slot_owner_details() {
+    let temp_producer = TemporaryBlockProducer::new();
+    temp_producer.next_block(vec![], false);
+
+    let blockchain = temp_producer.blockchain.read();
+    let block_number = blockchain.block_number() + 1;
+    let slot = blockchain
+        .get_proposer_at(block_number, block_number, None)
+        .unwrap();
+    let details = blockchain
+        .get_slot_owner_details_at(block_number, block_number, None)
+        .unwrap();
+
+    assert_eq!(details.slot.validator.address, slot.validator.address);
+    assert_eq!(details.num_slots, slot.validator.num_slots());
+    assert!(details.current_total_stake.is_some());
+}
+
+#[test]
+fn it_gets_accounts_at_retained_blocks() {
+    let temp_producer1 = TemporaryBlockProducer::new();
+    let temp_producer2 = TemporaryBlockProducer::new();
+    let key_pair = key_pair_with_funds();
+    let address = Address::from([1u8; Address::SIZE]);
+
+    let tx = |value: u64| {
+        TransactionBuilder::new_basic(
+            &key_pair,
+            address.clone(),
+            Coin::from_u64_unchecked(value),
+            Coin::ZERO,
+            1 + Policy::genesis_block_number(),
+            NetworkId::UnitAlbatross,
+        )
+        .unwrap()
+    };
+
+    // [0] - [1] - [2]
+    //          \- [0] - [0]
+    let genesis_hash = temp_producer1.blockchain.read().head_hash();
+    let block1 = temp_producer1.next_block_with_txs(vec![], false, vec![tx(100)]);
+    temp_producer2.push(block1.clone()).unwrap();
+    let block2 = temp_producer1.next_block_with_txs(vec![], false, vec![tx(200)]);
+
+    let fork1 = temp_producer2.next_block(vec![0x1], false);
+    let fork2 = temp_producer2.next_block(vec![], false);
+    assert_eq!(temp_producer1.push(fork1), Ok(PushResult::Forked));
+    assert_eq!(
+        temp_producer1.push(fork2.clone()),
+        Ok(PushResult::Rebranched)
+    );
+
+    let balance_at = |block_hash: &Blake2bHash| {
+        temp_producer1
+            .blockchain
+            .read()
+            .get_account_at_block(&address, block_hash, None)
+            .map(|account| account.unwrap().balance())
+    };
+
+    // Each block sees the balance right after it was applied, including the reorged out one.
+    assert_eq!(
+        balance_at(&block1.hash()),
+        Ok(Coin::from_u64_unchecked(100))
+    );
+    assert_eq!(
+        balance_at(&block2.hash()),
+        Ok(Coin::from_u64_unchecked(300))
+    );
+    assert_eq!(balance_at(&fork2.hash()), Ok(Coin::from_u64_unchecked(100)));
+
+    // The genesis block has no accounts diff, so its state can't be reconstructed.
+    assert_eq!(
+        balance_at(&genesis_hash),
+        Err(BlockchainError::AccountsStatePruned)
+    );
+
+    // Reading the state doesn't change the current one.
+    assert_eq!(
+        temp_producer1
+            .blockchain
+            .read()
+            .state
+            .accounts
+            .get_complete(&address, None)
+            .balance(),
+        Coin::from_u64_unchecked(100)
+    );
+}
@@ -1,4 +1,8 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use nimiq_block::Block;
 use nimiq_blockchain_interface::{AbstractBlockchain, PushResult};
+use nimiq_hash::Blake2bHash;
 use nimiq_primitives::policy::Policy;
 use nimiq_test_log::test;
 use nimiq_test_utils::block_production::TemporaryBlockProducer;
@@ -50,6 +54,72 @@ fn it_can_rebranch_skip_block() {
     assert_eq!(temp_producer1.push(fork2), Ok(PushResult::Extended));
 }
 
+#[test]
+fn it_finds_the_common_ancestor_of_a_fork() {
+    let temp_producer1 = TemporaryBlockProducer::new();
+    let temp_producer2 = TemporaryBlockProducer::new();
+
+    // [0] - [0] - [0]
+    //    \- [1] - [1]
+    let block = temp_producer1.next_block(vec![], false);
+    temp_producer2.push(block.clone()).unwrap();
+
+    let inferior1 = temp_producer1.next_block(vec![], false);
+    temp_producer2.next_block(vec![], true);
+
+    let inferior2 = temp_producer1.next_block(vec![], false);
+    temp_producer2.next_block(vec![], false);
+
+    // Producer 2 stores the inferior chain without rebranching.
+    assert_eq!(temp_producer2.push(inferior1), Ok(PushResult::Ignored));
+    assert_eq!(
+        temp_producer2.push(inferior2.clone()),
+        Ok(PushResult::Ignored)
+    );
+
+    let blockchain = temp_producer2.blockchain.read();
+    assert_eq!(
+        blockchain.find_common_ancestor(&inferior2.hash()),
+        Some((block.hash(), block.block_number()))
+    );
+    assert_eq!(
+        blockchain.find_common_ancestor(&blockchain.head_hash()),
+        Some((blockchain.head_hash(), blockchain.block_number()))
+    );
+    assert_eq!(
+        blockchain.find_common_ancestor(&Blake2bHash::default()),
+        None
+    );
+}
+
+#[test]
+fn it_breaks_ties_with_the_fork_tiebreak() {
+    let temp_producer1 = TemporaryBlockProducer::new();
+    let temp_producer2 = TemporaryBlockProducer::new();
+
+    // [0] - [0]
+    //    |- [0]
+    //    \- [0]
+    let block = temp_producer1.next_block(vec![], false);
+    temp_producer2.push(block).unwrap();
+
+    temp_producer1.next_block(vec![0x1], false);
+    let fork1 = temp_producer2.next_block_no_push(vec![0x2], false);
+    let fork2 = temp_producer2.next_block_no_push(vec![0x3], false);
+
+    // Without a tiebreak, the first seen chain is kept.
+    assert_eq!(temp_producer1.push(fork1), Ok(PushResult::Forked));
+
+    // The tiebreak adopts the chain of the new block.
+    temp_producer1.blockchain.write().fork_tiebreak =
+        Some(Arc::new(|_: &Block, _: &Block| Ordering::Greater));
+    assert_eq!(
+        temp_producer1.push(fork2.clone()),
+        Ok(PushResult::Rebranched)
+    );
+    assert_eq!(temp_producer1.blockchain.read().head_hash(), fork2.hash());
+}
+
 #[test]
 fn micro_block_works_after_macro_block() {
     let genesis_block_number = Policy::genesis_block_number();
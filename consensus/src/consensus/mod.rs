@@ -38,7 +38,7 @@ use crate::{
 #[cfg(feature = "full")]
 use crate::{
     messages::{
-        RequestBatchSet, RequestBlocksProof, RequestHistoryChunk,
+        RequestBatchSet, RequestBlocksProof, RequestHistoryChunk, RequestSubtreeCommitments,
         RequestTransactionReceiptsByAddress, RequestTransactionsProof, RequestTrieProof,
     },
     sync::live::{diff_queue::RequestTrieDiff, state_queue::RequestChunk},
@@ -264,6 +264,9 @@ impl<N: Network> Consensus<N> {
 
                 let stream = network.receive_requests::<RequestBlocksProof>();
                 spawn(Box::pin(request_handler(network, stream, blockchain)));
+
+                let stream = network.receive_requests::<RequestSubtreeCommitments>();
+                spawn(Box::pin(request_handler(network, stream, blockchain)));
             }
             BlockchainProxy::Light(_) => {}
         }
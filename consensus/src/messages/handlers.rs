@@ -710,6 +710,29 @@ impl<N: Network> Handle<N, Arc<RwLock<Blockchain>>> for RequestTrieProof {
     }
 }
 
+#[cfg(feature = "full")]
+impl<N: Network> Handle<N, Arc<RwLock<Blockchain>>> for RequestSubtreeCommitments {
+    fn handle(
+        &self,
+        _peer_id: N::PeerId,
+        blockchain: &Arc<RwLock<Blockchain>>,
+    ) -> Result<ResponseSubtreeCommitments, ResponseSubtreeCommitmentsError> {
+        if self.prefixes.len() > MAX_SUBTREE_COMMITMENTS_PREFIXES {
+            return Err(ResponseSubtreeCommitmentsError::TooManyPrefixes);
+        }
+
+        let blockchain = blockchain.read();
+
+        match blockchain.get_accounts_subtree_commitments(&self.prefixes) {
+            Err(IncompleteTrie) => Err(ResponseSubtreeCommitmentsError::IncompleteTrie),
+            Ok(commitments) => Ok(ResponseSubtreeCommitments {
+                commitments,
+                block_hash: blockchain.head_hash(),
+            }),
+        }
+    }
+}
+
 #[cfg(feature = "full")]
 impl<N: Network> Handle<N, Arc<RwLock<Blockchain>>> for RequestBlocksProof {
     fn handle(
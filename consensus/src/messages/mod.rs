@@ -50,6 +50,10 @@ pub const MAX_REQUEST_TRANSACTIONS_BY_ADDRESS: u32 = 1000;
 pub const MAX_REQUEST_TRIE_PROOF: u32 = 1000;
 /// The max number of Block proof requests per peer.
 pub const MAX_REQUEST_BLOCKS_PROOF: u32 = 1000;
+/// The max number of Subtree commitment requests per peer.
+pub const MAX_REQUEST_SUBTREE_COMMITMENTS: u32 = 1000;
+/// The max number of prefixes in a single Subtree commitment request.
+pub const MAX_SUBTREE_COMMITMENTS_PREFIXES: usize = 256;
 /// The max number of Subscribe to address requests per peer.
 pub const MAX_REQUEST_SUBSCRIBE_BY_ADDRESS: u32 = 10;
 /// The max number of Address notifications per peer.
@@ -448,6 +452,44 @@ pub enum ResponseTrieProofError {
     Other,
 }
 
+/// Request the commitments of the subtrees of the accounts trie below some prefixes. If a
+/// commitment matches the one of the local accounts trie, the subtree is identical and doesn't
+/// need to be transferred.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestSubtreeCommitments {
+    /// The key prefixes for which the subtree commitments are requested. At most
+    /// [`MAX_SUBTREE_COMMITMENTS_PREFIXES`] prefixes are answered.
+    pub prefixes: Vec<KeyNibbles>,
+}
+
+impl RequestCommon for RequestSubtreeCommitments {
+    type Kind = RequestMarker;
+    const TYPE_ID: u16 = 219;
+    type Response = Result<ResponseSubtreeCommitments, ResponseSubtreeCommitmentsError>;
+    const MAX_REQUESTS: u32 = MAX_REQUEST_SUBTREE_COMMITMENTS;
+}
+
+/// Response to [`RequestSubtreeCommitments`].
+#[derive(Serialize, Deserialize)]
+pub struct ResponseSubtreeCommitments {
+    /// The commitment for each requested prefix, in the same order. `None` if there are no
+    /// accounts below the prefix.
+    pub commitments: Vec<Option<Blake2bHash>>,
+    /// The hash of the block whose state the commitments were computed on.
+    pub block_hash: Blake2bHash,
+}
+
+#[derive(Clone, Debug, Deserialize, Error, Serialize)]
+pub enum ResponseSubtreeCommitmentsError {
+    #[error("incomplete trie")]
+    IncompleteTrie,
+    #[error("too many prefixes")]
+    TooManyPrefixes,
+    #[error("unknown error")]
+    #[serde(other)]
+    Other,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RequestBlocksProof {
     pub election_head: u32,
@@ -8,7 +8,10 @@ use nimiq_blockchain_interface::{AbstractBlockchain, PushResult};
 use nimiq_blockchain_proxy::BlockchainProxy;
 use nimiq_bls::cache::PublicKeyCache;
 use nimiq_consensus::{
-    messages::RequestMissingBlocks,
+    messages::{
+        RequestMissingBlocks, RequestSubtreeCommitments, ResponseSubtreeCommitmentsError,
+        MAX_SUBTREE_COMMITMENTS_PREFIXES,
+    },
     sync::{
         live::{
             block_queue::BlockQueue,
@@ -975,3 +978,44 @@ async fn replies_with_incomplete_response_chunk() {
         "Should not receive chunks from a peer with incomplete state"
     );
 }
+
+#[test]
+fn replies_with_subtree_commitments() {
+    let blockchain = Arc::new(RwLock::new(blockchain(true)));
+
+    let request = RequestSubtreeCommitments {
+        prefixes: vec![KeyNibbles::ROOT],
+    };
+    let response =
+        <RequestSubtreeCommitments as Handle<MockNetwork, Arc<RwLock<Blockchain>>>>::handle(
+            &request,
+            MockPeerId::from(1),
+            &blockchain,
+        )
+        .unwrap();
+
+    let blockchain_rg = blockchain.read();
+    assert_eq!(response.block_hash, blockchain_rg.head_hash());
+    assert_eq!(
+        response.commitments,
+        vec![blockchain_rg
+            .state
+            .accounts
+            .subtree_commitment(&KeyNibbles::ROOT, None)
+            .unwrap()]
+    );
+    drop(blockchain_rg);
+
+    // Requests with too many prefixes are rejected.
+    let request = RequestSubtreeCommitments {
+        prefixes: vec![KeyNibbles::ROOT; MAX_SUBTREE_COMMITMENTS_PREFIXES + 1],
+    };
+    assert!(matches!(
+        <RequestSubtreeCommitments as Handle<MockNetwork, Arc<RwLock<Blockchain>>>>::handle(
+            &request,
+            MockPeerId::from(1),
+            &blockchain,
+        ),
+        Err(ResponseSubtreeCommitmentsError::TooManyPrefixes)
+    ));
+}
@@ -24,6 +24,12 @@ where
         MdbxTransaction { txn }
     }
 
+    /// Returns the id of this transaction. For read-only transactions, this is the id of the
+    /// snapshot they are reading from.
+    pub fn id(&self) -> u64 {
+        self.txn.id()
+    }
+
     pub(super) fn open_table(&self, table: &MdbxTable) -> libmdbx::Table {
         self.txn.open_table(Some(&table.name)).unwrap()
     }
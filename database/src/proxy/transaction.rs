@@ -20,6 +20,17 @@ pub struct WriteTransactionProxy<'db> {
     txn: TransactionProxy<'db>,
 }
 
+impl<'db> TransactionProxy<'db> {
+    /// Returns the id of the database snapshot a read-only transaction is reading from.
+    /// Read-write transactions return `None`, as their view includes uncommitted changes.
+    pub fn snapshot_id(&self) -> Option<u64> {
+        match self {
+            TransactionProxy::ReadTransaction(txn) => Some(txn.id()),
+            TransactionProxy::WriteTransaction(_) => None,
+        }
+    }
+}
+
 impl<'db> ReadTransaction<'db> for TransactionProxy<'db> {
     type Table = TableProxy;
 
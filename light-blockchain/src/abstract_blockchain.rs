@@ -5,7 +5,8 @@ use nimiq_blockchain_interface::{
 };
 use nimiq_genesis::NetworkId;
 use nimiq_hash::Blake2bHash;
-use nimiq_primitives::slots_allocation::{Slot, Validators};
+use nimiq_primitives::slots_allocation::{Slot, SlotOwnerDetails, Validators};
+use nimiq_transaction::Transaction;
 use tokio_stream::wrappers::BroadcastStream;
 
 use crate::blockchain::LightBlockchain;
@@ -78,6 +79,11 @@ impl AbstractBlockchain for LightBlockchain {
             .get_blocks(start_block_hash, count, direction, include_body)
     }
 
+    fn get_transaction(&self, _hash: &Blake2bHash) -> Option<(Transaction, Blake2bHash)> {
+        // The light blockchain doesn't keep the history of transactions.
+        None
+    }
+
     fn get_chain_info(
         &self,
         hash: &Blake2bHash,
@@ -91,6 +97,22 @@ impl AbstractBlockchain for LightBlockchain {
         self.get_proposer(block_number, offset, vrf_entropy)
     }
 
+    fn get_slot_owner_details_at(
+        &self,
+        block_number: u32,
+        offset: u32,
+    ) -> Result<SlotOwnerDetails, BlockchainError> {
+        let slot = self.get_proposer_at(block_number, offset)?;
+        let num_slots = slot.validator.num_slots();
+
+        // The light blockchain doesn't have the staking contract.
+        Ok(SlotOwnerDetails {
+            slot,
+            num_slots,
+            current_total_stake: None,
+        })
+    }
+
     fn get_proposer_of(&self, block_hash: &Blake2bHash) -> Result<Slot, BlockchainError> {
         let block = self.get_block(block_hash, false)?;
         let vrf_entropy = self.get_block(block.parent_hash(), false)?.seed().entropy();
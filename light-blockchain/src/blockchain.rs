@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use nimiq_block::{Block, MacroBlock};
 use nimiq_blockchain_interface::{
-    AbstractBlockchain, BlockchainError, BlockchainEvent, ChainInfo, ForkEvent,
+    AbstractBlockchain, BlockchainError, BlockchainEvent, ChainInfo, ForkEvent, ForkTiebreak,
 };
 use nimiq_genesis::NetworkInfo;
 use nimiq_primitives::{
@@ -41,6 +41,8 @@ pub struct LightBlockchain {
     pub notifier: BroadcastSender<BlockchainEvent>,
     /// The fork notifier processes fork events.
     pub fork_notifier: BroadcastSender<ForkEvent>,
+    /// Decides between competing chains of equal length. If `None`, the first seen chain is kept.
+    pub fork_tiebreak: Option<ForkTiebreak>,
 }
 
 /// Implements methods to start a Blockchain.
@@ -76,6 +78,7 @@ impl LightBlockchain {
             chain_store,
             notifier: tx,
             fork_notifier: tx_fork,
+            fork_tiebreak: None,
         }
     }
 
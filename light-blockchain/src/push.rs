@@ -57,6 +57,7 @@ impl LightBlockchain {
             this.deref(),
             &block,
             &prev_info,
+            this.fork_tiebreak.as_ref(),
             |hash| this.get_chain_info(hash, false),
             |height| this.get_block_at(height, false),
         );
@@ -4,6 +4,7 @@ use nimiq_blockchain::Blockchain;
 use nimiq_blockchain_interface::AbstractBlockchain;
 use nimiq_hash::Hash;
 use nimiq_primitives::{networks::NetworkId, transaction::TransactionError};
+use nimiq_serde::Serialize;
 use nimiq_transaction::Transaction;
 use parking_lot::RwLock;
 use thiserror::Error;
@@ -74,10 +75,14 @@ pub(crate) async fn verify_tx(
         return Err(VerifyErr::Known);
     }
 
-    // 6. Check if the transaction is going to be filtered.
+    // 6. Check if the transaction is going to be filtered. The minimum fee of the consensus rules
+    // applies regardless of the filter rules.
     {
         let filter = filter.read();
-        if !filter.accepts_transaction(transaction) || filter.blacklisted(&transaction.hash()) {
+        if transaction.fee < blockchain.min_transaction_fee(transaction.serialized_size())
+            || !filter.accepts_transaction(transaction)
+            || filter.blacklisted(&transaction.hash())
+        {
             // FIXME add transaction to blacklist
             log::debug!("Transaction filtered");
             return Err(VerifyErr::Filtered);
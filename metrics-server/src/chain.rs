@@ -43,10 +43,42 @@ impl BlockMetrics {
         }));
         sub_registry.register("accounts", "Number of accounts", closure);
 
+        let bc = blockchain.clone();
         let closure = NumericClosureMetric::new_gauge(Box::new(move || {
-            blockchain.read().state.accounts.num_branches() as i64
+            bc.read().state.accounts.num_branches() as i64
         }));
         sub_registry.register("num_branches", "Number of branch nodes", closure);
+
+        let counters: [(&str, &str, fn(&Blockchain) -> u64); 6] = [
+            ("commits", "Number of committed blocks", |bc| {
+                bc.state.accounts.metrics().commits
+            }),
+            ("reverts", "Number of reverted blocks", |bc| {
+                bc.state.accounts.metrics().reverts
+            }),
+            ("node_cache_hits", "Number of node cache hits", |bc| {
+                bc.state.accounts.metrics().node_cache_hits
+            }),
+            ("node_cache_misses", "Number of node cache misses", |bc| {
+                bc.state.accounts.metrics().node_cache_misses
+            }),
+            (
+                "validity_cache_hits",
+                "Number of validity cache hits",
+                |bc| bc.state.accounts.metrics().validity_cache_hits,
+            ),
+            (
+                "validity_cache_misses",
+                "Number of validity cache misses",
+                |bc| bc.state.accounts.metrics().validity_cache_misses,
+            ),
+        ];
+        for (name, help, counter) in counters {
+            let bc = blockchain.clone();
+            let closure =
+                NumericClosureMetric::new_gauge(Box::new(move || counter(&bc.read()) as i64));
+            sub_registry.register(name, help, closure);
+        }
     }
 
     fn register_chain(registry: &mut Registry, blockchain: BlockchainProxy) {
@@ -37,6 +37,12 @@ pub struct HashedTimeLockedContract {
 
 #[cfg(feature = "interaction-traits")]
 impl HashedTimeLockedContract {
+    /// Returns whether the contract has timed out at the given time, i.e. whether it can only be
+    /// resolved by the sender anymore.
+    pub(crate) fn has_expired(&self, time: u64) -> bool {
+        self.timeout < time
+    }
+
     fn can_change_balance(
         &self,
         transaction: &Transaction,
@@ -55,7 +61,7 @@ impl HashedTimeLockedContract {
                 signature_proof,
             } => {
                 // Check that the contract has not expired yet.
-                if self.timeout < block_state.time {
+                if self.has_expired(block_state.time) {
                     warn!("HTLC has expired: {} < {}", self.timeout, block_state.time);
                     return Err(AccountError::InvalidForSender);
                 }
@@ -112,7 +118,7 @@ impl HashedTimeLockedContract {
                 signature_proof_sender,
             } => {
                 // Check that the contract has expired.
-                if self.timeout >= block_state.time {
+                if !self.has_expired(block_state.time) {
                     warn!(
                         "HTLC has not yet expired: {} >= {}",
                         self.timeout, block_state.time
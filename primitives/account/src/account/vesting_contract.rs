@@ -78,6 +78,25 @@ impl VestingContract {
             Coin::ZERO
         }
     }
+
+    /// Returns whether the whole `total_amount` has been released at the given time.
+    pub(crate) fn is_fully_vested(&self, time: u64) -> bool {
+        self.min_cap(time).is_zero()
+    }
+
+    /// Returns the time at which the min cap enforced by `min_cap` drops to zero, i.e. at which
+    /// the whole `total_amount` has been released.
+    pub(crate) fn fully_vested_at(&self) -> u64 {
+        if self.time_step > 0 && self.step_amount > Coin::ZERO {
+            let total_amount = u64::from(self.total_amount);
+            let step_amount = u64::from(self.step_amount);
+            let steps = total_amount.div_ceil(step_amount);
+            self.start_time
+                .saturating_add(steps.saturating_mul(self.time_step))
+        } else {
+            0
+        }
+    }
 }
 
 #[cfg(feature = "interaction-traits")]
@@ -1,34 +1,200 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap, HashMap, HashSet},
+    io::Write,
+    thread,
+    time::{Duration, Instant},
+};
+
 use nimiq_database::{
-    traits::{Database, WriteTransaction},
-    DatabaseProxy, TransactionProxy as DBTransaction,
+    traits::{Database, ReadTransaction, WriteTransaction},
+    DatabaseProxy, TableFlags, TableProxy, TransactionProxy as DBTransaction,
 };
 use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_keys::Address;
 use nimiq_primitives::{
     account::{AccountError, AccountType, FailReason},
+    coin::Coin,
     key_nibbles::KeyNibbles,
+    networks::NetworkId,
+    policy::Policy,
+    slots_allocation::Validators,
+    transaction::TransactionError,
     trie::{
-        error::IncompleteTrie,
+        error::{IncompleteTrie, MerkleRadixTrieError},
         trie_chunk::{TrieChunk, TrieChunkPushResult},
-        trie_diff::TrieDiff,
+        trie_diff::{TrieDiff, ValueChange},
         trie_proof::TrieProof,
         TrieItem,
     },
     TreeProof,
 };
-use nimiq_transaction::{inherent::Inherent, ExecutedTransaction, Transaction, TransactionFlags};
-use nimiq_trie::{trie::MerkleRadixTrie, WriteTransactionProxy};
+use nimiq_serde::{Deserialize, Serialize};
+use nimiq_transaction::{
+    account::AccountTransactionVerification, inherent::Inherent, ExecutedTransaction, Transaction,
+    TransactionFlags,
+};
+use nimiq_trie::{trie::MerkleRadixTrie, IoStats, TrieNodeCache, WriteTransactionProxy};
+use nimiq_vrf::VrfSeed;
 
 use crate::{
-    Account, AccountInherentInteraction, AccountPruningInteraction, AccountReceipt,
-    AccountTransactionInteraction, BlockLogger, BlockState, DataStore, InherentLogger,
-    InherentOperationReceipt, OperationReceipt, Receipts, ReservedBalance, RevertInfo,
-    TransactionLog, TransactionOperationReceipt, TransactionReceipt,
+    metrics::BlockCounters, validity_cache::ValidityCache, Account, AccountInherentInteraction,
+    AccountPruningInteraction, AccountReceipt, AccountTransactionInteraction, AccountsMetrics,
+    BlockLogger, BlockState, DataStore, InherentLogger, InherentOperationReceipt, OperationReceipt,
+    Receipts, ReservedBalance, RevertInfo, TransactionLog, TransactionOperationReceipt,
+    TransactionReceipt,
 };
 
 /// An alias for the accounts tree.
 pub type AccountsTrie = MerkleRadixTrie;
 
+/// Describes the effect a single transaction would have on the accounts tree, as computed by
+/// `Accounts::explain_transaction`.
+#[derive(Clone, Debug)]
+pub struct TransactionExplanation {
+    /// The sender account before the transaction is applied.
+    pub sender_before: Account,
+    /// The sender account after the transaction is applied.
+    pub sender_after: Account,
+    /// The recipient account before the transaction is applied.
+    pub recipient_before: Account,
+    /// The recipient account after the transaction is applied.
+    pub recipient_after: Account,
+    /// The fee paid by the transaction.
+    pub fee: Coin,
+    /// The value transferred by the transaction.
+    pub value: Coin,
+    /// The receipt of the transaction. Failed transactions only deduct the fee from the sender.
+    pub receipt: TransactionOperationReceipt,
+}
+
+/// A primitive change to the accounts trie, as recorded by `Accounts::commit_with_oplog`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrieOp {
+    /// The value at the key was set to a new value. The old value is `None` if the key didn't
+    /// exist before.
+    Put(KeyNibbles, Option<Vec<u8>>, Vec<u8>),
+    /// The value at the key was removed.
+    Remove(KeyNibbles, Vec<u8>),
+}
+
+/// The primitive trie operations performed by a commit, in ascending key order. Every key
+/// appears at most once, with the net change to its value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpLog(pub Vec<TrieOp>);
+
+/// An overview of the staking contract, as returned by `Accounts::staking_summary`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StakingSummary {
+    /// The total amount of coins staked, including the validator deposits.
+    pub total_staked: Coin,
+    /// The number of validators that are eligible to receive slots.
+    pub active_validators: usize,
+    /// The number of validators that are not eligible to receive slots, e.g. because they are
+    /// deactivated, jailed or retired.
+    pub inactive_validators: usize,
+}
+
+/// A change to a single account, as reconstructed by `Accounts::account_history`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccountChange {
+    /// The account sent the transaction at `index` in the block. Failed transactions are included
+    /// as well, since the sender still pays the fee.
+    SentTransaction {
+        index: u16,
+        receipt: TransactionOperationReceipt,
+    },
+    /// The account received the transaction at `index` in the block.
+    ReceivedTransaction {
+        index: u16,
+        receipt: TransactionOperationReceipt,
+    },
+    /// The account was the target of the inherent at `index` in the block.
+    Inherent {
+        index: u16,
+        receipt: InherentOperationReceipt,
+    },
+}
+
+/// The reason why a contract is considered dormant by `Accounts::find_dormant_contracts`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DormancyReason {
+    /// The HTLC has timed out, so the recipient can't resolve it anymore and its funds can only
+    /// be reclaimed by the sender.
+    HtlcExpired,
+    /// The vesting contract has released its whole `total_amount`, so it doesn't restrict the
+    /// owner anymore.
+    FullyVested,
+}
+
+/// The part of a block that made `Accounts::commit_checked` fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitStep {
+    /// The transaction at the given index in the block.
+    Transaction(usize),
+    /// The inherent at the given index in the block.
+    Inherent(usize),
+}
+
+/// A receipt passed to the callback of `Accounts::commit_batch_with`.
+enum CommitReceipt {
+    Transaction(TransactionOperationReceipt),
+    Inherent(InherentOperationReceipt),
+}
+
+/// Limits that are enforced by `Accounts::commit_with_options` in addition to the checks of
+/// `commit`. The default options don't impose any limits.
+#[derive(Clone, Copy, Debug)]
+pub struct CommitOptions {
+    /// The maximum number of inherents a block may contain.
+    pub max_inherents: usize,
+    /// Whether the proofs of the transactions are verified before they are committed. `commit`
+    /// assumes that this was already done when the block was validated, so this is only needed
+    /// when the accounts are used without the blockchain.
+    pub verify_signatures: bool,
+}
+
+impl Default for CommitOptions {
+    fn default() -> Self {
+        CommitOptions {
+            max_inherents: usize::MAX,
+            verify_signatures: false,
+        }
+    }
+}
+
+/// Limits the rate at which the transactions of blocks committed with `Accounts::commit_paced`
+/// are written back to the accounts tree. The same pacing should be passed to consecutive commits
+/// so that the rate applies across blocks.
+#[derive(Clone, Debug)]
+pub struct CommitPacing {
+    /// The write rate in bytes per second. Once the transactions committed so far exceed it,
+    /// `commit_paced` waits before writing back the next transaction until the rate is back at
+    /// this threshold.
+    pub max_bytes_per_second: u64,
+    start: Instant,
+    bytes_written: u64,
+}
+
+impl CommitPacing {
+    pub fn new(max_bytes_per_second: u64) -> Self {
+        Self {
+            max_bytes_per_second,
+            start: Instant::now(),
+            bytes_written: 0,
+        }
+    }
+
+    /// Returns how long to wait before writing back the next transaction so that the
+    /// transactions committed so far stay within the configured rate.
+    pub fn delay(&self) -> Duration {
+        let target = Duration::from_secs_f64(
+            self.bytes_written as f64 / self.max_bytes_per_second.max(1) as f64,
+        );
+        target.saturating_sub(self.start.elapsed())
+    }
+}
+
 /// The Accounts struct is simply an wrapper containing a database environment and, more importantly,
 /// a MerkleRadixTrie with accounts as leaf values. This struct basically holds all the accounts in
 /// the blockchain. It also has methods to commit and revert transactions, so we can use it to
@@ -37,13 +203,89 @@ pub type AccountsTrie = MerkleRadixTrie;
 pub struct Accounts {
     pub env: DatabaseProxy,
     pub tree: AccountsTrie,
+    /// If set, committing a transaction for any other network fails.
+    network_id: Option<NetworkId>,
+    /// If set, the accounts tree roots of the most recent blocks.
+    root_history: Option<RootHistory>,
+    /// If set, the transactions found to be valid by `validate_all` on top of the current state.
+    validity_cache: Option<ValidityCache>,
+    /// The number of blocks committed and reverted, as reported by `metrics`.
+    block_counters: BlockCounters,
+}
+
+/// The accounts tree roots after the most recent blocks, indexed by block number. The roots are
+/// kept in a database table so that they are rolled back together with the write transaction that
+/// recorded them.
+#[derive(Debug)]
+struct RootHistory {
+    table: TableProxy,
+    size: u32,
 }
 
 impl Accounts {
     /// Creates a new Accounts.
     pub fn new(env: DatabaseProxy) -> Self {
         let tree = AccountsTrie::new(env.clone(), "AccountsTrie");
-        Accounts { env, tree }
+        Accounts {
+            env,
+            tree,
+            network_id: None,
+            root_history: None,
+            validity_cache: None,
+            block_counters: BlockCounters::default(),
+        }
+    }
+
+    /// Creates a new Accounts whose trie shares a cache of up to `cache_capacity` nodes across
+    /// read transactions. The cache statistics are available through `tree.node_cache()`.
+    pub fn with_node_cache(env: DatabaseProxy, cache_capacity: usize) -> Self {
+        let tree = AccountsTrie::new_with_node_cache(env.clone(), "AccountsTrie", cache_capacity);
+        Accounts {
+            env,
+            tree,
+            network_id: None,
+            root_history: None,
+            validity_cache: None,
+            block_counters: BlockCounters::default(),
+        }
+    }
+
+    /// Makes commits fail with `AccountError::TrieTooDeep` if an account would be stored more than
+    /// `max_depth` nodes below the root of the Accounts Trie. For the regular address space this
+    /// never triggers, but it bounds the length of lookup paths for adversarially chosen addresses.
+    pub fn with_max_trie_depth(mut self, max_depth: usize) -> Self {
+        self.tree = self.tree.with_max_depth(max_depth);
+        self
+    }
+
+    /// Keeps the accounts tree roots of the last `size` committed blocks, which can then be looked
+    /// up using `recent_root`. The roots are recorded by `commit` and `commit_streaming_receipts`
+    /// and removed again by `revert`.
+    pub fn with_root_history(mut self, size: u32) -> Self {
+        let table = self
+            .env
+            .open_table_with_flags("AccountsRootHistory".to_string(), TableFlags::UINT_KEYS);
+        self.root_history = Some(RootHistory { table, size });
+        self
+    }
+
+    /// Makes `validate_all` remember up to `capacity` transactions that were found to be valid, so
+    /// that validating them again on top of the same state with the same block number and time
+    /// is skipped. The cache is dropped as soon as the accounts tree root changes.
+    pub fn with_validity_cache(mut self, capacity: usize) -> Self {
+        self.validity_cache = Some(ValidityCache::new(capacity));
+        self
+    }
+
+    /// Returns the number of transactions currently held by the validity cache, if enabled.
+    pub fn validity_cache_len(&self) -> Option<usize> {
+        self.validity_cache.as_ref().map(ValidityCache::len)
+    }
+
+    /// Makes commits reject transactions that are not for the given network.
+    pub fn with_network_id(mut self, network_id: NetworkId) -> Self {
+        self.network_id = Some(network_id);
+        self
     }
 
     /// Initializes the Accounts struct with a given list of accounts.
@@ -51,6 +293,20 @@ impl Accounts {
         self.tree.init(txn, genesis_accounts)
     }
 
+    /// Initializes the Accounts struct like `init`, but consumes the genesis accounts from an
+    /// iterator, so that a large genesis doesn't need to be held in memory at once. The items are
+    /// written to the tree as they are consumed and the root hashes are computed once at the end.
+    /// Returns the resulting accounts tree root, or an error if the tree is not empty and
+    /// complete or an item can't be stored, in which case the transaction should be aborted.
+    pub fn init_streaming(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        genesis_accounts: impl IntoIterator<Item = TrieItem>,
+    ) -> Result<Blake2bHash, AccountError> {
+        self.tree.try_init(txn, genesis_accounts)?;
+        Ok(self.get_root_hash_assert(Some(txn)))
+    }
+
     /// Returns the number of accounts (incl. hybrid nodes) in the Accounts Trie.
     pub fn size(&self) -> u64 {
         let txn = self.env.read_transaction();
@@ -62,6 +318,47 @@ impl Accounts {
         self.tree.num_branches(&self.env.read_transaction())
     }
 
+    /// Returns the number of node reads, writes and root updates performed on the Accounts Trie
+    /// since it was created or the statistics were last reset. Resetting the statistics before a
+    /// commit reveals the node operations caused by a single block.
+    pub fn io_stats(&self) -> IoStats {
+        self.tree.io_stats()
+    }
+
+    /// Resets the statistics returned by `io_stats` to zero.
+    pub fn reset_io_stats(&self) {
+        self.tree.reset_io_stats()
+    }
+
+    /// Returns a snapshot of the cache, commit and I/O counters of the accounts, e.g. to export
+    /// them to a monitoring system.
+    pub fn metrics(&self) -> AccountsMetrics {
+        let node_cache = self.tree.node_cache();
+        let validity_cache = self.validity_cache.as_ref();
+        AccountsMetrics {
+            node_cache_hits: node_cache.map_or(0, TrieNodeCache::hits),
+            node_cache_misses: node_cache.map_or(0, TrieNodeCache::misses),
+            validity_cache_hits: validity_cache.map_or(0, ValidityCache::hits),
+            validity_cache_misses: validity_cache.map_or(0, ValidityCache::misses),
+            commits: self.block_counters.commits(),
+            reverts: self.block_counters.reverts(),
+            io: self.io_stats(),
+        }
+    }
+
+    /// Adds the given number of blocks to the `commits` counter of `metrics`. Commits don't count
+    /// themselves, as they may happen in write transactions that are aborted afterwards, so the
+    /// caller must call this once the blocks are persisted.
+    pub fn count_committed_blocks(&self, count: u64) {
+        self.block_counters.count_commits(count);
+    }
+
+    /// Adds the given number of blocks to the `reverts` counter of `metrics`. Like for
+    /// `count_committed_blocks`, the caller must call this once the reverts are persisted.
+    pub fn count_reverted_blocks(&self, count: u64) {
+        self.block_counters.count_reverts(count);
+    }
+
     pub fn get(
         &self,
         address: &Address,
@@ -82,6 +379,309 @@ impl Accounts {
             .expect("Tree must be complete")
     }
 
+    /// Returns the account at the given address together with the depth of its node in the
+    /// accounts tree, i.e. the number of nodes below the root on the path to it. Unlike `get`,
+    /// this returns `None` if there is no account at the address.
+    pub fn get_with_depth(
+        &self,
+        address: &Address,
+        txn_option: Option<&DBTransaction>,
+    ) -> Result<Option<(Account, u8)>, IncompleteTrie> {
+        let key = KeyNibbles::from(address);
+        let account = match txn_option {
+            Some(txn) => self.tree.get_with_depth(txn, &key)?,
+            None => self
+                .tree
+                .get_with_depth(&self.env.read_transaction(), &key)?,
+        };
+        // The depth is bounded by the number of nibbles in a key, so it always fits.
+        Ok(account.map(|(account, depth)| (account, depth as u8)))
+    }
+
+    /// Returns the accounts at the given keys, aligned with `keys` including duplicates. Unlike
+    /// `get`, this returns `None` if there is no account at a key. All accounts are read in the
+    /// same transaction and every distinct key is only looked up once, by reading its node
+    /// directly rather than walking the tree (see `MerkleRadixTrie::get_many`).
+    pub fn get_many(
+        &self,
+        keys: &[KeyNibbles],
+        txn_option: Option<&DBTransaction>,
+    ) -> Result<Vec<Option<Account>>, IncompleteTrie> {
+        match txn_option {
+            Some(txn) => self.tree.get_many(txn, keys),
+            None => self.tree.get_many(&self.env.read_transaction(), keys),
+        }
+    }
+
+    /// Checks whether the account at the given address has a balance of at least `amount`.
+    /// Returns false if the account is in the missing part of an incomplete tree.
+    pub fn has_balance_at_least(
+        &self,
+        address: &Address,
+        amount: Coin,
+        txn_option: Option<&DBTransaction>,
+    ) -> bool {
+        match self.get(address, txn_option) {
+            Ok(account) => account.balance() >= amount,
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the time in milliseconds left until the vesting contract at the given address has
+    /// released its whole `total_amount`, following the same schedule that is enforced when
+    /// committing transactions. The schedule is based on block timestamps rather than block
+    /// numbers, so `current_time` is a timestamp as well. An estimate in blocks can be obtained by
+    /// dividing the result by `Policy::BLOCK_SEPARATION_TIME`. Returns `Some(0)` if the contract
+    /// is already fully vested and `None` if the account is not a vesting contract or is in the
+    /// missing part of an incomplete tree.
+    pub fn vesting_remaining_time(
+        &self,
+        address: &Address,
+        current_time: u64,
+        txn_option: Option<&DBTransaction>,
+    ) -> Option<u64> {
+        match self.get(address, txn_option) {
+            Ok(Account::Vesting(contract)) => {
+                Some(contract.fully_vested_at().saturating_sub(current_time))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the total stake and the number of active and inactive validators of the staking
+    /// contract. Only the validators of the staking contract are iterated, not the whole tree.
+    /// Returns all zeros if there is no staking contract or it is in the missing part of an
+    /// incomplete tree.
+    pub fn staking_summary(&self, txn_option: Option<&DBTransaction>) -> StakingSummary {
+        let read_txn: DBTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = self.env.read_transaction();
+                &read_txn
+            }
+        };
+
+        let staking_contract = match self.get(&Policy::STAKING_CONTRACT_ADDRESS, Some(txn)) {
+            Ok(Account::Staking(staking_contract)) => staking_contract,
+            _ => return StakingSummary::default(),
+        };
+
+        let data_store = self.data_store(&Policy::STAKING_CONTRACT_ADDRESS);
+        let num_validators = staking_contract.get_validators(&data_store.read(txn)).len();
+        let active_validators = staking_contract.active_validators.len();
+
+        StakingSummary {
+            total_staked: staking_contract.balance,
+            active_validators,
+            inactive_validators: num_validators - active_validators,
+        }
+    }
+
+    /// Distributes the validator slots across the active validators of the staking contract using
+    /// the given seed, exactly like the validators of an election block are selected. Returns
+    /// `None` if there is no staking contract or it is in the missing part of an incomplete tree.
+    pub fn compute_validators(
+        &self,
+        seed: &VrfSeed,
+        txn_option: Option<&DBTransaction>,
+    ) -> Option<Validators> {
+        let read_txn: DBTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = self.env.read_transaction();
+                &read_txn
+            }
+        };
+
+        match self.get(&Policy::STAKING_CONTRACT_ADDRESS, Some(txn)) {
+            Ok(Account::Staking(staking_contract)) => {
+                let data_store = self.data_store(&Policy::STAKING_CONTRACT_ADDRESS);
+                Some(staking_contract.select_validators(&data_store.read(txn), seed))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns all contracts that are dormant at the given time, in ascending order of address.
+    /// A contract is dormant once its terminal condition, as checked when committing
+    /// transactions, is reached. Iterates over all accounts in the tree.
+    pub fn find_dormant_contracts(
+        &self,
+        current_time: u64,
+        txn_option: Option<&DBTransaction>,
+    ) -> Vec<(Address, DormancyReason)> {
+        let read_txn: DBTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = self.env.read_transaction();
+                &read_txn
+            }
+        };
+
+        self.iter_accounts(txn)
+            .filter_map(|(address, account)| match account {
+                Account::HTLC(contract) if contract.has_expired(current_time) => {
+                    Some((address, DormancyReason::HtlcExpired))
+                }
+                Account::Vesting(contract) if contract.is_fully_vested(current_time) => {
+                    Some((address, DormancyReason::FullyVested))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Checks the invariants of the accounts at the given addresses, e.g. the ones changed by the
+    /// last block, and returns the first violation found. Only the given accounts are read, so
+    /// this is cheap enough to run after every block. The checked invariants are:
+    /// - no account that should have been pruned is stored,
+    /// - the staking contract is stored at the staking contract address and nowhere else,
+    /// - the hash count of a HTLC is not zero,
+    /// - the balance of a vesting contract doesn't exceed its total amount.
+    ///
+    /// That no basic account has a negative balance is not checked, as it is unrepresentable:
+    /// `Coin` can't hold negative or out of range values. Returns `AccountError::ChunkError` if one of the accounts is in the missing part of
+    /// an incomplete tree.
+    pub fn verify_invariants(
+        &self,
+        changed: &[Address],
+        txn_option: Option<&DBTransaction>,
+    ) -> Result<(), AccountError> {
+        let read_txn: DBTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = self.env.read_transaction();
+                &read_txn
+            }
+        };
+
+        for address in changed {
+            let account = self
+                .tree
+                .get(txn, &KeyNibbles::from(address))
+                .map_err(|_| MerkleRadixTrieError::IncompleteTrie)?;
+
+            let is_staking_address = *address == Policy::STAKING_CONTRACT_ADDRESS;
+            let violation = match account {
+                None if is_staking_address => Some("staking contract is missing"),
+                None => None,
+                Some(account) if account.can_be_pruned() => Some("account is not pruned"),
+                Some(account)
+                    if is_staking_address != (account.account_type() == AccountType::Staking) =>
+                {
+                    Some("staking contract is not at the staking contract address")
+                }
+                Some(Account::HTLC(contract)) if contract.hash_count == 0 => {
+                    Some("hash count is zero")
+                }
+                Some(Account::Vesting(contract)) if contract.balance > contract.total_amount => {
+                    Some("balance exceeds the total amount")
+                }
+                Some(_) => None,
+            };
+
+            if let Some(invariant) = violation {
+                return Err(AccountError::InvariantViolation {
+                    address: address.clone(),
+                    invariant,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the `n` accounts with the highest balance, in descending order of balance.
+    /// Accounts with equal balance are ordered by ascending address.
+    pub fn top_accounts_by_balance(
+        &self,
+        n: usize,
+        txn_option: Option<&DBTransaction>,
+    ) -> Vec<(Address, Coin)> {
+        let read_txn: DBTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = self.env.read_transaction();
+                &read_txn
+            }
+        };
+
+        if n == 0 {
+            return vec![];
+        }
+
+        // We keep a min-heap of the largest balances seen so far. For equal balances, the
+        // account with the largest address is evicted first.
+        let mut heap = BinaryHeap::with_capacity(n + 1);
+        for (address, account) in self.iter_accounts(txn) {
+            heap.push(Reverse((account.balance(), Reverse(address))));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((balance, Reverse(address)))| (address, balance))
+            .collect()
+    }
+
+    /// Returns the total size in bytes of the serialized accounts, grouped by account type.
+    /// Only the accounts themselves are taken into account, not the data stores of contracts.
+    pub fn storage_by_type(&self, txn_option: Option<&DBTransaction>) -> HashMap<AccountType, u64> {
+        let read_txn: DBTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = self.env.read_transaction();
+                &read_txn
+            }
+        };
+
+        let mut storage = HashMap::new();
+        for (_, value) in self.tree.iter_values_with_len(txn, 2 * Address::SIZE) {
+            let account =
+                Account::deserialize_from_vec(&value).expect("Failed to deserialize account");
+            *storage.entry(account.account_type()).or_default() += value.len() as u64;
+        }
+        storage
+    }
+
+    /// Iterates over all accounts in ascending key order. The accounts are streamed from the
+    /// database as the iterator advances, and the data stores of contracts are skipped. The order
+    /// only depends on the keys, so it is the same across commits and two iterations over
+    /// different states can be merged to compute a diff. If the accounts tree is incomplete, only
+    /// the accounts in its complete part are returned.
+    pub fn iter<'txn>(
+        &self,
+        txn: &'txn DBTransaction,
+    ) -> impl Iterator<Item = (KeyNibbles, Account)> + 'txn {
+        self.tree
+            .iter_values_with_len(txn, 2 * Address::SIZE)
+            .map(|(key, value)| {
+                let account =
+                    Account::deserialize_from_vec(&value).expect("Failed to deserialize account");
+                (key, account)
+            })
+    }
+
+    /// Iterates over all accounts in the tree in ascending address order.
+    /// Entries of the contracts' data stores are skipped.
+    fn iter_accounts<'txn>(
+        &self,
+        txn: &'txn DBTransaction,
+    ) -> impl Iterator<Item = (Address, Account)> + 'txn {
+        self.iter(txn).map(|(key, account)| {
+            let address = key.to_address().expect("Key must be an address");
+            (address, account)
+        })
+    }
+
     pub fn reserve_balance(
         &self,
         account: &Account,
@@ -173,11 +773,88 @@ impl Accounts {
         }
     }
 
-    fn put(&self, txn: &mut WriteTransactionProxy, address: &Address, account: Account) {
+    /// Credits the given amounts to the given basic accounts, creating them if they don't exist
+    /// yet, and returns the new root hash. This bypasses transactions entirely and is only meant
+    /// for tooling like testnet faucets, hence it is gated behind the `bulk-credit` feature.
+    #[cfg(feature = "bulk-credit")]
+    pub fn bulk_credit(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        credits: &[(Address, Coin)],
+    ) -> Result<Blake2bHash, AccountError> {
+        assert!(self.is_complete(Some(txn)), "Tree must be complete");
+
+        for (address, value) in credits {
+            let account = self.get_complete(address, Some(txn));
+            let balance = match account {
+                Account::Basic(basic) => basic.balance,
+                _ => {
+                    return Err(AccountError::TypeMismatch {
+                        expected: AccountType::Basic,
+                        got: account.account_type(),
+                    })
+                }
+            };
+            let balance = balance
+                .checked_add(*value)
+                .ok_or(AccountError::InvalidCoinValue)?;
+            self.put_or_prune(
+                txn,
+                address,
+                Account::Basic(crate::BasicAccount { balance }),
+            )?;
+        }
+
+        self.tree.update_root(txn).expect("Tree must be complete");
+        Ok(self.get_root_hash_assert(Some(txn)))
+    }
+
+    /// Commits the given transactions and inherents like `commit`, but checks the accounts at the
+    /// addresses in `overrides` against the given type instead of the sender or recipient type of
+    /// the transaction. This is only meant for one-time migration tooling, hence it is gated
+    /// behind the `migration` feature. Note that the block logs refer to the hashes of the
+    /// transactions with the overridden types.
+    #[cfg(feature = "migration")]
+    pub fn commit_with_type_overrides(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        block_logger: &mut BlockLogger,
+        overrides: &HashMap<Address, AccountType>,
+    ) -> Result<Receipts, AccountError> {
+        let transactions: Vec<Transaction> = transactions
+            .iter()
+            .map(|transaction| {
+                let mut transaction = transaction.clone();
+                if let Some(ty) = overrides.get(&transaction.sender) {
+                    transaction.sender_type = *ty;
+                }
+                if let Some(ty) = overrides.get(&transaction.recipient) {
+                    transaction.recipient_type = *ty;
+                }
+                transaction
+            })
+            .collect();
+
+        self.commit(txn, &transactions, inherents, block_state, block_logger)
+    }
+
+    fn put(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        address: &Address,
+        account: Account,
+    ) -> Result<(), AccountError> {
         assert!(!account.can_be_pruned());
-        self.tree
-            .put(txn, &KeyNibbles::from(address), account)
-            .expect("Failed to put account into tree")
+        match self.tree.put(txn, &KeyNibbles::from(address), account) {
+            Ok(()) => Ok(()),
+            Err(MerkleRadixTrieError::TrieTooDeep(max_depth)) => {
+                Err(AccountError::TrieTooDeep { max_depth })
+            }
+            Err(e) => panic!("Failed to put account into tree: {e}"),
+        }
     }
 
     fn put_or_prune(
@@ -185,15 +862,15 @@ impl Accounts {
         txn: &mut WriteTransactionProxy,
         address: &Address,
         account: Account,
-    ) -> Option<AccountReceipt> {
+    ) -> Result<Option<AccountReceipt>, AccountError> {
         if account.can_be_pruned() {
             let store = DataStore::new(&self.tree, address);
             let pruned_account = account.prune(store.read(txn));
             self.prune(txn, address);
-            pruned_account
+            Ok(pruned_account)
         } else {
-            self.put(txn, address, account);
-            None
+            self.put(txn, address, account)?;
+            Ok(None)
         }
     }
 
@@ -209,6 +886,13 @@ impl Accounts {
         }
     }
 
+    /// Returns a Pedersen commitment to the current state, computed with the same parameters as
+    /// the zkp circuits (see `nimiq_zkp_primitives::state_commitment`).
+    #[cfg(feature = "zkp")]
+    pub fn pedersen_state_commitment(&self, txn_option: Option<&DBTransaction>) -> [u8; 95] {
+        nimiq_zkp_primitives::state_commitment(&self.get_root_hash_assert(txn_option))
+    }
+
     pub fn get_root_hash(&self, txn_option: Option<&DBTransaction>) -> Option<Blake2bHash> {
         match txn_option {
             Some(txn) => self.tree.root_hash(txn),
@@ -247,20 +931,469 @@ impl Accounts {
                 .expect("Failed to update within missing part");
         }
 
-        missing
+        missing
+    }
+
+    pub fn exercise_transactions(
+        &self,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+    ) -> Result<(Blake2bHash, Blake2bHash, Vec<ExecutedTransaction>), AccountError> {
+        let mut raw_txn = self.env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        assert!(self.is_complete(Some(&txn)), "Tree must be complete");
+
+        txn.start_recording();
+        let receipts = self.commit(
+            &mut txn,
+            transactions,
+            inherents,
+            block_state,
+            &mut BlockLogger::empty(),
+        )?;
+        let diff = txn.stop_recording().into_forward_diff();
+        let diff_hash = TreeProof::new(diff.0).root_hash();
+
+        assert_eq!(transactions.len(), receipts.transactions.len());
+
+        let executed_txns = transactions
+            .iter()
+            .zip(receipts.transactions.iter())
+            .map(|(tx, receipt)| match receipt {
+                OperationReceipt::Ok(_) => ExecutedTransaction::Ok(tx.clone()),
+                OperationReceipt::Err(..) => ExecutedTransaction::Err(tx.clone()),
+            })
+            .collect();
+
+        let state_hash = self.get_root_hash_assert(Some(&txn));
+
+        raw_txn.abort();
+
+        Ok((state_hash, diff_hash, executed_txns))
+    }
+
+    /// Applies a single transaction on top of the current state and reports the sender and
+    /// recipient accounts before and after, together with the resulting receipt. All changes are
+    /// made in a write transaction that is aborted afterwards, so the state is left untouched.
+    pub fn explain_transaction(
+        &self,
+        transaction: &Transaction,
+        block_state: &BlockState,
+    ) -> Result<TransactionExplanation, AccountError> {
+        let mut raw_txn = self.env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        assert!(self.is_complete(Some(&txn)), "Tree must be complete");
+
+        let sender_before = self.get_complete(&transaction.sender, Some(&txn));
+        let recipient_before = self.get_complete(&transaction.recipient, Some(&txn));
+
+        let receipt = self.commit_transaction(
+            &mut txn,
+            transaction,
+            block_state,
+            &mut TransactionLog::empty(),
+        )?;
+
+        let sender_after = self.get_complete(&transaction.sender, Some(&txn));
+        let recipient_after = self.get_complete(&transaction.recipient, Some(&txn));
+
+        raw_txn.abort();
+
+        Ok(TransactionExplanation {
+            sender_before,
+            sender_after,
+            recipient_before,
+            recipient_after,
+            fee: transaction.fee,
+            value: transaction.value,
+            receipt,
+        })
+    }
+
+    /// Applies a single transaction on top of the current state and returns the resulting sender
+    /// account, e.g. to preview the state of a contract after an outgoing transaction. Unlike in a
+    /// block, a transaction that can't be applied is not committed as failed but returns its
+    /// error. All changes are made in a write transaction that is aborted afterwards.
+    pub fn simulate_contract_tx(
+        &self,
+        transaction: &Transaction,
+        block_state: &BlockState,
+    ) -> Result<Account, AccountError> {
+        let mut raw_txn = self.env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        assert!(self.is_complete(Some(&txn)), "Tree must be complete");
+
+        self.try_commit_transaction(
+            &mut txn,
+            transaction,
+            block_state,
+            &mut TransactionLog::empty(),
+        )?;
+        let sender = self.get_complete(&transaction.sender, Some(&txn));
+
+        raw_txn.abort();
+
+        Ok(sender)
+    }
+
+    /// Performs the checks on a block body that don't require access to the accounts tree and
+    /// returns the first problem found. It checks that no transaction appears twice, that the
+    /// fields of each transaction are consistent (see `Transaction::verify_fields`), that no
+    /// reward is paid to the staking contract and that the inherents are ordered by
+    /// `Inherent::ordering_class`. Signatures are not verified.
+    pub fn pre_validate(
+        network_id: NetworkId,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+    ) -> Result<(), AccountError> {
+        let mut hashes = HashSet::with_capacity(transactions.len());
+        for (index, transaction) in transactions.iter().enumerate() {
+            if !hashes.insert(transaction.hash::<Blake2bHash>()) {
+                return Err(AccountError::DuplicateTransaction {
+                    index: index as u16,
+                });
+            }
+            transaction.verify_fields(network_id)?;
+        }
+
+        for inherent in inherents {
+            if let Inherent::Reward { target, .. } = inherent {
+                if *target == Policy::STAKING_CONTRACT_ADDRESS {
+                    return Err(AccountError::InvalidInherent);
+                }
+            }
+        }
+        Self::check_inherent_order(inherents).map_err(|(_, error)| error)?;
+
+        Ok(())
+    }
+
+    /// Returns the index and a reference of every transaction whose sender or recipient is the
+    /// staking contract. This only looks at the addresses and doesn't require the accounts tree.
+    pub fn staking_transactions(transactions: &[Transaction]) -> Vec<(u16, &Transaction)> {
+        transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, transaction)| {
+                transaction.sender == Policy::STAKING_CONTRACT_ADDRESS
+                    || transaction.recipient == Policy::STAKING_CONTRACT_ADDRESS
+            })
+            .map(|(index, transaction)| (index as u16, transaction))
+            .collect()
+    }
+
+    /// Returns the distinct targets of the given inherents. Inherents that don't have an explicit
+    /// target, like penalties or the finalization of a batch, target the staking contract.
+    pub fn inherent_targets(inherents: &[Inherent]) -> HashSet<Address> {
+        inherents
+            .iter()
+            .map(|inherent| inherent.target().clone())
+            .collect()
+    }
+
+    /// Reconstructs the changes the account at `address` underwent in the given blocks. Each block
+    /// is given by its number, its transactions and inherents and the receipts that were produced
+    /// when committing them. Receipts don't contain any addresses, so the transactions and
+    /// inherents are needed to match them to the account.
+    ///
+    /// The changes are returned in the order in which they were applied, i.e. by block, with the
+    /// transactions of a block before its inherents. Failed transactions only affect the sender
+    /// and failed inherents don't affect any account, so they are left out otherwise.
+    pub fn account_history(
+        address: &Address,
+        blocks: &[(u32, &[Transaction], &[Inherent], &Receipts)],
+    ) -> Vec<(u32, AccountChange)> {
+        let mut history = vec![];
+
+        for (block_number, transactions, inherents, receipts) in blocks {
+            for (index, (transaction, receipt)) in
+                transactions.iter().zip(&receipts.transactions).enumerate()
+            {
+                let index = index as u16;
+                if &transaction.sender == address {
+                    let change = AccountChange::SentTransaction {
+                        index,
+                        receipt: receipt.clone(),
+                    };
+                    history.push((*block_number, change));
+                }
+                if &transaction.recipient == address
+                    && matches!(receipt, TransactionOperationReceipt::Ok(_))
+                {
+                    let change = AccountChange::ReceivedTransaction {
+                        index,
+                        receipt: receipt.clone(),
+                    };
+                    history.push((*block_number, change));
+                }
+            }
+
+            for (index, (inherent, receipt)) in
+                inherents.iter().zip(&receipts.inherents).enumerate()
+            {
+                if inherent.target() == address
+                    && matches!(receipt, InherentOperationReceipt::Ok(_))
+                {
+                    let change = AccountChange::Inherent {
+                        index: index as u16,
+                        receipt: receipt.clone(),
+                    };
+                    history.push((*block_number, change));
+                }
+            }
+        }
+
+        history
+    }
+
+    /// Commits the given transactions and inherents without updating the accounts tree root, in
+    /// a write transaction that is aborted afterwards. This checks whether `commit` would succeed
+    /// on top of the current state while skipping the root computation, which is the most
+    /// expensive part of a commit. Failing transactions don't make the block invalid, as they are
+    /// committed with a failed receipt.
+    ///
+    /// The block can't be applied to an in-memory overlay of the touched accounts instead, as
+    /// contracts like the staking contract keep their data in the accounts tree and only operate
+    /// on it through a `DataStore` in a write transaction.
+    pub fn commit_without_root_update(
+        &self,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+    ) -> Result<(), AccountError> {
+        let mut raw_txn = self.env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+
+        let result = self.commit_batch(
+            &mut txn,
+            transactions,
+            inherents,
+            block_state,
+            &mut BlockLogger::empty(),
+        );
+
+        raw_txn.abort();
+        result.map(|_| ())
+    }
+
+    /// Applies each of the given transactions on its own on top of the current state and returns
+    /// the index and error of every transaction that fails. Each transaction is applied in a
+    /// separate write transaction that is aborted afterwards, so a failing transaction doesn't
+    /// affect the others and the state is left untouched. If the validity cache is enabled (see
+    /// `with_validity_cache`), transactions that were already found to be valid are skipped.
+    pub fn validate_all(
+        &self,
+        transactions: &[Transaction],
+        block_state: &BlockState,
+    ) -> Vec<(u16, AccountError)> {
+        let mut failures = vec![];
+        let cache = self
+            .validity_cache
+            .as_ref()
+            .map(|cache| (cache, self.get_root_hash_assert(None)));
+
+        for (index, transaction) in transactions.iter().enumerate() {
+            let key = (
+                transaction.hash::<Blake2bHash>(),
+                block_state.number,
+                block_state.time,
+            );
+            if let Some((cache, root)) = &cache {
+                if cache.is_valid(root, &key) {
+                    continue;
+                }
+            }
+
+            let mut raw_txn = self.env.write_transaction();
+            let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+            assert!(self.is_complete(Some(&txn)), "Tree must be complete");
+
+            match self.try_commit_transaction(
+                &mut txn,
+                transaction,
+                block_state,
+                &mut TransactionLog::empty(),
+            ) {
+                Ok(_) => {
+                    if let Some((cache, root)) = &cache {
+                        cache.insert_valid(root, key);
+                    }
+                }
+                Err(error) => failures.push((index as u16, error)),
+            }
+
+            raw_txn.abort();
+        }
+
+        failures
+    }
+
+    /// Selects transactions from `pending` for a new block. Transactions are considered in
+    /// descending order of fee per byte and are included if they fit within `max_size` bytes and
+    /// apply successfully on top of the current state and the transactions selected before them.
+    /// All changes are made in a write transaction that is aborted afterwards.
+    ///
+    /// The transactions are expected to be verified already, i.e. signatures and validity windows
+    /// are not checked here.
+    pub fn pack_block(
+        &self,
+        pending: &[Transaction],
+        max_size: usize,
+        block_state: &BlockState,
+    ) -> Vec<Transaction> {
+        let mut raw_txn = self.env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        assert!(self.is_complete(Some(&txn)), "Tree must be complete");
+
+        let mut candidates: Vec<&Transaction> = pending.iter().collect();
+        candidates.sort_by(|a, b| b.fee_per_byte().total_cmp(&a.fee_per_byte()));
+
+        let mut selected = vec![];
+        let mut size = 0;
+        for transaction in candidates {
+            let tx_size = transaction.serialized_size();
+            if size + tx_size > max_size {
+                continue;
+            }
+
+            // Failing transactions don't change the state.
+            if self
+                .try_commit_transaction(
+                    &mut txn,
+                    transaction,
+                    block_state,
+                    &mut TransactionLog::empty(),
+                )
+                .is_ok()
+            {
+                size += tx_size;
+                selected.push(transaction.clone());
+            }
+        }
+
+        raw_txn.abort();
+
+        selected
+    }
+
+    /// Computes the change in total supply, i.e. the sum of all account balances, caused by
+    /// committing the given transactions and inherents on top of the current state. All changes
+    /// are made in a write transaction that is aborted afterwards.
+    ///
+    /// Fees only move funds around, so for a valid block this equals the block reward.
+    pub fn supply_delta(
+        &self,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+    ) -> Result<i128, AccountError> {
+        let mut raw_txn = self.env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        assert!(self.is_complete(Some(&txn)), "Tree must be complete");
+
+        let supply_before = self.total_balance(&txn);
+        self.commit(
+            &mut txn,
+            transactions,
+            inherents,
+            block_state,
+            &mut BlockLogger::empty(),
+        )?;
+        let supply_after = self.total_balance(&txn);
+
+        raw_txn.abort();
+
+        Ok(supply_after - supply_before)
+    }
+
+    /// Returns the addresses of all accounts that don't exist yet and would be created by
+    /// committing the given transactions and inherents on top of the current state, in ascending
+    /// order. All changes are made in a write transaction that is aborted afterwards.
+    pub fn new_addresses(
+        &self,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+    ) -> Result<Vec<Address>, AccountError> {
+        let mut raw_txn = self.env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        assert!(self.is_complete(Some(&txn)), "Tree must be complete");
+
+        txn.start_recording();
+        self.commit(
+            &mut txn,
+            transactions,
+            inherents,
+            block_state,
+            &mut BlockLogger::empty(),
+        )?;
+        let changes = txn.stop_recording().changes;
+
+        raw_txn.abort();
+
+        // Data stores of contracts live under longer keys, so only account keys yield an address.
+        Ok(changes
+            .into_iter()
+            .filter_map(|(key, change)| match change {
+                ValueChange::Insert(_) => key.to_address(),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Sums up the balances of all accounts.
+    fn total_balance(&self, txn: &DBTransaction) -> i128 {
+        self.iter_accounts(txn)
+            .map(|(_, account)| i128::from(u64::from(account.balance())))
+            .sum()
+    }
+
+    pub fn commit(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        block_logger: &mut BlockLogger,
+    ) -> Result<Receipts, AccountError> {
+        self.commit_checked(txn, transactions, inherents, block_state, block_logger)
+            .map_err(|(_, error)| error)
+    }
+
+    /// Commits the given transactions and inherents like `commit`, but on error additionally
+    /// returns the transaction or inherent that caused it. Failing transactions still produce a
+    /// failed receipt, so this only concerns the errors that make the whole block invalid. As for
+    /// `commit`, the write transaction must be aborted on error.
+    pub fn commit_checked(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        block_logger: &mut BlockLogger,
+    ) -> Result<Receipts, (CommitStep, AccountError)> {
+        let receipts =
+            self.commit_batch_checked(txn, transactions, inherents, block_state, block_logger)?;
+        self.tree.update_root(txn).expect("Tree must be complete");
+        self.record_root(txn, block_state.number);
+        Ok(receipts)
     }
 
-    pub fn exercise_transactions(
+    /// Commits the given transactions and inherents like `commit` in a write transaction of its
+    /// own. The changes are only persisted if the resulting accounts tree root equals
+    /// `expected_root`. Otherwise, the write transaction is aborted and
+    /// `AccountError::RootMismatch` is returned.
+    pub fn commit_if_root(
         &self,
         transactions: &[Transaction],
         inherents: &[Inherent],
         block_state: &BlockState,
-    ) -> Result<(Blake2bHash, Blake2bHash, Vec<ExecutedTransaction>), AccountError> {
+        expected_root: &Blake2bHash,
+    ) -> Result<Receipts, AccountError> {
         let mut raw_txn = self.env.write_transaction();
         let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
-        assert!(self.is_complete(Some(&txn)), "Tree must be complete");
 
-        txn.start_recording();
         let receipts = self.commit(
             &mut txn,
             transactions,
@@ -268,39 +1401,133 @@ impl Accounts {
             block_state,
             &mut BlockLogger::empty(),
         )?;
-        let diff = txn.stop_recording().into_forward_diff();
-        let diff_hash = TreeProof::new(diff.0).root_hash();
+        let root = self.get_root_hash_assert(Some(&txn));
 
-        assert_eq!(transactions.len(), receipts.transactions.len());
+        if root != *expected_root {
+            raw_txn.abort();
+            return Err(AccountError::RootMismatch {
+                expected: expected_root.clone(),
+                got: root,
+            });
+        }
 
-        let executed_txns = transactions
-            .iter()
-            .zip(receipts.transactions.iter())
-            .map(|(tx, receipt)| match receipt {
-                OperationReceipt::Ok(_) => ExecutedTransaction::Ok(tx.clone()),
-                OperationReceipt::Err(..) => ExecutedTransaction::Err(tx.clone()),
-            })
-            .collect();
+        raw_txn.commit();
+        Ok(receipts)
+    }
 
-        let state_hash = self.get_root_hash_assert(Some(&txn));
+    /// Commits the given transactions and inherents like `commit`, but first checks them against
+    /// the given options. A block with more than `options.max_inherents` inherents is rejected
+    /// with `AccountError::TooManyInherents` before anything is committed. If
+    /// `options.verify_signatures` is set, the transactions are verified first: one with an
+    /// invalid proof is rejected with `AccountError::InvalidTransactionSignature`, and one that
+    /// fails verification for another reason with `AccountError::InvalidTransaction`.
+    pub fn commit_with_options(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        block_logger: &mut BlockLogger,
+        options: &CommitOptions,
+    ) -> Result<Receipts, AccountError> {
+        if inherents.len() > options.max_inherents {
+            return Err(AccountError::TooManyInherents {
+                count: inherents.len(),
+            });
+        }
 
-        raw_txn.abort();
+        if options.verify_signatures {
+            for (index, transaction) in transactions.iter().enumerate() {
+                AccountType::verify_outgoing_transaction(transaction)
+                    .and_then(|_| AccountType::verify_incoming_transaction(transaction))
+                    .map_err(|error| match error {
+                        TransactionError::InvalidProof => {
+                            AccountError::InvalidTransactionSignature {
+                                index: index as u16,
+                            }
+                        }
+                        error => AccountError::InvalidTransaction(error),
+                    })?;
+            }
+        }
 
-        Ok((state_hash, diff_hash, executed_txns))
+        self.commit(txn, transactions, inherents, block_state, block_logger)
     }
 
-    pub fn commit(
+    /// Commits the given transactions and inherents like `commit` and additionally returns the
+    /// primitive trie operations that were performed. The operation log can be used to undo the
+    /// commit with `apply_oplog_reverse`, independently of the receipts.
+    pub fn commit_with_oplog(
         &self,
         txn: &mut WriteTransactionProxy,
         transactions: &[Transaction],
         inherents: &[Inherent],
         block_state: &BlockState,
         block_logger: &mut BlockLogger,
-    ) -> Result<Receipts, AccountError> {
-        let receipts =
-            self.commit_batch(txn, transactions, inherents, block_state, block_logger)?;
+    ) -> Result<(Receipts, OpLog), AccountError> {
+        txn.start_recording();
+        let result = self.commit(txn, transactions, inherents, block_state, block_logger);
+        let changes = txn.stop_recording().changes;
+        let receipts = result?;
+
+        let oplog = changes
+            .into_iter()
+            .map(|(key, change)| match change {
+                ValueChange::Insert(new) => TrieOp::Put(key, None, new),
+                ValueChange::Update(old, new) => TrieOp::Put(key, Some(old), new),
+                ValueChange::Delete(old) => TrieOp::Remove(key, old),
+            })
+            .collect();
+
+        Ok((receipts, OpLog(oplog)))
+    }
+
+    /// Commits the given transactions and inherents like `commit` and additionally returns the
+    /// addresses whose accounts or account data were changed, including inherent targets and
+    /// created contracts. Addresses that were only read, or whose changes cancelled out within
+    /// the block, are not included.
+    pub fn commit_with_touched(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        block_logger: &mut BlockLogger,
+    ) -> Result<(Receipts, HashSet<Address>), AccountError> {
+        txn.start_recording();
+        let result = self.commit(txn, transactions, inherents, block_state, block_logger);
+        let changes = txn.stop_recording().changes;
+        let receipts = result?;
+
+        // Data stores of contracts live under longer keys, so they are attributed to the contract.
+        let touched = changes
+            .keys()
+            .filter_map(|key| key.slice(0, 2 * Address::len()).to_address())
+            .collect();
+
+        Ok((receipts, touched))
+    }
+
+    /// Undoes the operations of the given operation log, restoring the state from before the
+    /// commit that produced it.
+    pub fn apply_oplog_reverse(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        oplog: OpLog,
+    ) -> Result<(), AccountError> {
+        let diff = oplog
+            .0
+            .into_iter()
+            .rev()
+            .map(|op| match op {
+                TrieOp::Put(key, old, _) => (key, old),
+                TrieOp::Remove(key, old) => (key, Some(old)),
+            })
+            .collect();
+
+        self.tree.apply_diff(txn, TrieDiff(diff))?;
         self.tree.update_root(txn).expect("Tree must be complete");
-        Ok(receipts)
+        Ok(())
     }
 
     pub fn commit_incomplete(
@@ -313,6 +1540,56 @@ impl Accounts {
         Ok(diff)
     }
 
+    /// Commits the given transactions and inherents like `commit`. If `pacing` is given, the size
+    /// of each transaction is added to it after the transaction was written back, and the commit
+    /// waits for `CommitPacing::delay` before writing back the next one. This smooths out write
+    /// bursts when committing many large blocks back-to-back. Pacing only affects timing; the
+    /// resulting state is the same as for `commit`.
+    pub fn commit_paced(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        block_logger: &mut BlockLogger,
+        pacing: Option<&mut CommitPacing>,
+    ) -> Result<Receipts, AccountError> {
+        let Some(pacing) = pacing else {
+            return self.commit(txn, transactions, inherents, block_state, block_logger);
+        };
+
+        let mut receipts = Receipts::default();
+        let mut transaction_sizes = transactions
+            .iter()
+            .map(|transaction| transaction.serialized_size() as u64);
+        self.commit_batch_with(
+            txn,
+            transactions,
+            inherents,
+            block_state,
+            block_logger,
+            &mut |receipt| {
+                match receipt {
+                    CommitReceipt::Transaction(receipt) => {
+                        receipts.transactions.push(receipt);
+                        pacing.bytes_written += transaction_sizes.next().unwrap_or_default();
+                        let delay = pacing.delay();
+                        if !delay.is_zero() {
+                            thread::sleep(delay);
+                        }
+                    }
+                    CommitReceipt::Inherent(receipt) => receipts.inherents.push(receipt),
+                }
+                Ok(())
+            },
+        )
+        .map_err(|(_, error)| error)?;
+
+        self.tree.update_root(txn).expect("Tree must be complete");
+        self.record_root(txn, block_state.number);
+        Ok(receipts)
+    }
+
     pub fn commit_batch(
         &self,
         txn: &mut WriteTransactionProxy,
@@ -321,30 +1598,191 @@ impl Accounts {
         block_state: &BlockState,
         block_logger: &mut BlockLogger,
     ) -> Result<Receipts, AccountError> {
-        assert!(self.is_complete(Some(txn)), "Tree must be complete");
+        self.commit_batch_checked(txn, transactions, inherents, block_state, block_logger)
+            .map_err(|(_, error)| error)
+    }
+
+    fn commit_batch_checked(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        block_logger: &mut BlockLogger,
+    ) -> Result<Receipts, (CommitStep, AccountError)> {
         let mut receipts = Receipts::default();
+        self.commit_batch_with(
+            txn,
+            transactions,
+            inherents,
+            block_state,
+            block_logger,
+            &mut |receipt| {
+                match receipt {
+                    CommitReceipt::Transaction(receipt) => receipts.transactions.push(receipt),
+                    CommitReceipt::Inherent(receipt) => receipts.inherents.push(receipt),
+                }
+                Ok(())
+            },
+        )?;
+        Ok(receipts)
+    }
 
-        for transaction in transactions {
-            let receipt = self.commit_transaction(
-                txn,
-                transaction,
-                block_state,
-                block_logger.new_tx_log(transaction.hash()),
-            )?;
-            receipts.transactions.push(receipt);
+    /// Commits the given transactions and inherents without updating the tree root, passing
+    /// each receipt to `on_receipt` as soon as it is produced. An error returned by `on_receipt`
+    /// is attributed to the transaction or inherent whose receipt was passed.
+    fn commit_batch_with(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        block_logger: &mut BlockLogger,
+        on_receipt: &mut dyn FnMut(CommitReceipt) -> Result<(), AccountError>,
+    ) -> Result<(), (CommitStep, AccountError)> {
+        assert!(self.is_complete(Some(txn)), "Tree must be complete");
+
+        Self::check_inherent_order(inherents)?;
+
+        for (index, transaction) in transactions.iter().enumerate() {
+            self.check_network_id(index, transaction)
+                .and_then(|_| {
+                    self.commit_transaction(
+                        txn,
+                        transaction,
+                        block_state,
+                        block_logger.new_tx_log(transaction.hash()),
+                    )
+                })
+                .and_then(|receipt| on_receipt(CommitReceipt::Transaction(receipt)))
+                .map_err(|error| (CommitStep::Transaction(index), error))?;
         }
 
-        for inherent in inherents {
-            let receipt = self.commit_inherent(
+        for (index, inherent) in inherents.iter().enumerate() {
+            self.commit_inherent(
                 txn,
                 inherent,
                 block_state,
                 &mut block_logger.inherent_logger(),
-            )?;
-            receipts.inherents.push(receipt);
+            )
+            .and_then(|receipt| on_receipt(CommitReceipt::Inherent(receipt)))
+            .map_err(|error| (CommitStep::Inherent(index), error))?;
         }
 
-        Ok(receipts)
+        Ok(())
+    }
+
+    /// Checks that the given inherents are ordered by non-decreasing `Inherent::ordering_class`.
+    /// Returns the position of the first inherent that is out of order otherwise.
+    fn check_inherent_order(inherents: &[Inherent]) -> Result<(), (CommitStep, AccountError)> {
+        let position = inherents
+            .windows(2)
+            .position(|pair| pair[1].ordering_class() < pair[0].ordering_class());
+        match position {
+            Some(position) => Err((
+                CommitStep::Inherent(position + 1),
+                AccountError::InvalidInherentOrder {
+                    index: (position + 1) as u16,
+                },
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Commits the given transactions and inherents like `commit`, but instead of collecting all
+    /// receipts in memory, each receipt is serialized into `receipt_sink` as soon as it is
+    /// produced. The stream starts with the `Receipts::VERSION` byte, followed by the transaction
+    /// receipts and then the inherent receipts.
+    /// This bounds the memory used for receipts when committing very large blocks.
+    pub fn commit_streaming_receipts(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        block_logger: &mut BlockLogger,
+        mut receipt_sink: &mut dyn Write,
+    ) -> Result<(), AccountError> {
+        receipt_sink
+            .write_all(&[Receipts::VERSION])
+            .map_err(|e| AccountError::ReceiptSink(e.to_string()))?;
+
+        self.commit_batch_with(
+            txn,
+            transactions,
+            inherents,
+            block_state,
+            block_logger,
+            &mut |receipt| {
+                match receipt {
+                    CommitReceipt::Transaction(receipt) => {
+                        receipt.serialize_to_writer(&mut receipt_sink)
+                    }
+                    CommitReceipt::Inherent(receipt) => {
+                        receipt.serialize_to_writer(&mut receipt_sink)
+                    }
+                }
+                .map(|_| ())
+                .map_err(|e| AccountError::ReceiptSink(e.to_string()))
+            },
+        )
+        .map_err(|(_, error)| error)?;
+
+        self.tree.update_root(txn).expect("Tree must be complete");
+        self.record_root(txn, block_state.number);
+        Ok(())
+    }
+
+    /// Returns the accounts tree root after the block at the given height, if it is among the
+    /// recent blocks kept by the root history. Always returns `None` if the root history is not
+    /// enabled.
+    pub fn recent_root(
+        &self,
+        height: u32,
+        txn_option: Option<&DBTransaction>,
+    ) -> Option<Blake2bHash> {
+        let root_history = self.root_history.as_ref()?;
+        match txn_option {
+            Some(txn) => txn.get(&root_history.table, &height),
+            None => self
+                .env
+                .read_transaction()
+                .get(&root_history.table, &height),
+        }
+    }
+
+    fn record_root(&self, txn: &mut WriteTransactionProxy, height: u32) {
+        if let Some(root_history) = &self.root_history {
+            let root = self.get_root_hash_assert(Some(txn));
+            let raw_txn = txn.raw();
+            raw_txn.put(&root_history.table, &height, &root);
+            if let Some(oldest) = height.checked_sub(root_history.size) {
+                raw_txn.remove(&root_history.table, &oldest);
+            }
+        }
+    }
+
+    fn forget_root(&self, txn: &mut WriteTransactionProxy, height: u32) {
+        if let Some(root_history) = &self.root_history {
+            txn.raw().remove(&root_history.table, &height);
+        }
+    }
+
+    fn check_network_id(
+        &self,
+        index: usize,
+        transaction: &Transaction,
+    ) -> Result<(), AccountError> {
+        match self.network_id {
+            Some(expected) if transaction.network_id != expected => {
+                Err(AccountError::WrongNetwork {
+                    expected,
+                    got: transaction.network_id,
+                    index: index as u16,
+                })
+            }
+            _ => Ok(()),
+        }
     }
 
     fn commit_transaction(
@@ -356,6 +1794,9 @@ impl Accounts {
     ) -> Result<TransactionOperationReceipt, AccountError> {
         match self.try_commit_transaction(txn, transaction, block_state, tx_logger) {
             Ok(receipt) => Ok(TransactionOperationReceipt::Ok(receipt)),
+            // The trie might already have been partially updated, so the transaction can't be
+            // treated as a failed transaction.
+            Err(e @ AccountError::TrieTooDeep { .. }) => Err(e),
             Err(e) => {
                 let fail_reason = FailReason::from(e);
                 tx_logger.clear();
@@ -419,10 +1860,10 @@ impl Accounts {
         }
 
         // Update or prune sender.
-        let pruned_account = self.put_or_prune(txn, sender_address, sender_account);
+        let pruned_account = self.put_or_prune(txn, sender_address, sender_account)?;
 
         // Update recipient.
-        self.put(txn, recipient_address, recipient_account);
+        self.put(txn, recipient_address, recipient_account)?;
 
         Ok(TransactionReceipt {
             sender_receipt,
@@ -503,7 +1944,7 @@ impl Accounts {
             tx_logger,
         )?;
 
-        let pruned_account = self.put_or_prune(txn, sender_address, sender_account);
+        let pruned_account = self.put_or_prune(txn, sender_address, sender_account)?;
 
         Ok(TransactionReceipt {
             sender_receipt,
@@ -531,7 +1972,7 @@ impl Accounts {
             account.commit_inherent(inherent, block_state, store.write(txn), inherent_logger);
         match result {
             Ok(receipt) => {
-                self.put(txn, address, account);
+                self.put(txn, address, account)?;
                 Ok(InherentOperationReceipt::Ok(receipt))
             }
             Err(e) => Ok(InherentOperationReceipt::Err(None, e.into())),
@@ -563,9 +2004,109 @@ impl Accounts {
             }
         }
         self.tree.update_root(txn).ok();
+        self.forget_root(txn, block_state.number);
+        Ok(())
+    }
+
+    /// Reverts a single transaction given its receipt and returns the resulting accounts tree
+    /// root. Both the sender and the recipient are reverted, after checking that their account
+    /// types still match the transaction. Only the nodes changed by the transaction are rehashed.
+    /// Unlike `revert`, this doesn't revert a whole block, so the root history is left untouched.
+    pub fn revert_single(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        transaction: &Transaction,
+        block_state: &BlockState,
+        receipt: TransactionOperationReceipt,
+    ) -> Result<Blake2bHash, AccountError> {
+        assert!(self.is_complete(Some(txn)), "Tree must be complete");
+
+        self.revert_transaction(
+            txn,
+            transaction,
+            block_state,
+            receipt,
+            &mut TransactionLog::empty(),
+        )?;
+        self.tree.update_root(txn).expect("Tree must be complete");
+
+        Ok(self.get_root_hash_assert(Some(txn)))
+    }
+
+    /// Reverts the given block on top of the current state and checks that this results in the
+    /// accounts tree root `expected_prior_root`, e.g. to validate the receipts of a claimed reorg.
+    /// All changes are made in a write transaction that is aborted afterwards.
+    pub fn verify_revert_to(
+        &self,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        revert_info: RevertInfo,
+        expected_prior_root: &Blake2bHash,
+    ) -> Result<(), AccountError> {
+        let mut raw_txn = self.env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        assert!(self.is_complete(Some(&txn)), "Tree must be complete");
+
+        self.revert(
+            &mut txn,
+            transactions,
+            inherents,
+            block_state,
+            revert_info,
+            &mut BlockLogger::empty(),
+        )?;
+        let root = self.get_root_hash_assert(Some(&txn));
+
+        raw_txn.abort();
+
+        if root != *expected_prior_root {
+            return Err(AccountError::RootMismatch {
+                expected: expected_prior_root.clone(),
+                got: root,
+            });
+        }
         Ok(())
     }
 
+    /// Reverts the given block on top of the current state and returns the resulting accounts tree
+    /// root together with the addresses whose accounts (or account data) would be changed by the
+    /// revert, in ascending order. All changes are made in a write transaction that is aborted
+    /// afterwards, so the state is left untouched.
+    pub fn revert_dry_run(
+        &self,
+        transactions: &[Transaction],
+        inherents: &[Inherent],
+        block_state: &BlockState,
+        revert_info: RevertInfo,
+    ) -> Result<(Blake2bHash, Vec<Address>), AccountError> {
+        let mut raw_txn = self.env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        assert!(self.is_complete(Some(&txn)), "Tree must be complete");
+
+        txn.start_recording();
+        let result = self.revert(
+            &mut txn,
+            transactions,
+            inherents,
+            block_state,
+            revert_info,
+            &mut BlockLogger::empty(),
+        );
+        let changes = txn.stop_recording().changes;
+        let root = self.get_root_hash_assert(Some(&txn));
+
+        raw_txn.abort();
+        result?;
+
+        let addresses: BTreeSet<Address> = changes
+            .keys()
+            .filter_map(|key| key.slice(0, 2 * Address::len()).to_address())
+            .collect();
+
+        Ok((root, addresses.into_iter().collect()))
+    }
+
     pub fn revert_diff(
         &self,
         txn: &mut WriteTransactionProxy,
@@ -584,6 +2125,14 @@ impl Accounts {
         receipts: Receipts,
         block_logger: &mut BlockLogger,
     ) -> Result<(), AccountError> {
+        if receipts.version() > Receipts::VERSION {
+            return Err(AccountError::UnsupportedReceiptVersion {
+                version: receipts.version(),
+                supported: Receipts::VERSION,
+            });
+        }
+        Self::check_inherent_order(inherents).map_err(|(_, error)| error)?;
+
         // Revert inherents in reverse order.
         assert_eq!(inherents.len(), receipts.inherents.len());
         let iter = inherents.iter().zip(receipts.inherents).rev();
@@ -685,7 +2234,7 @@ impl Accounts {
 
             // Update or prune recipient.
             // The recipient account might have been created by the incoming transaction.
-            self.put_or_prune(txn, recipient_address, recipient_account);
+            self.put_or_prune(txn, recipient_address, recipient_account)?;
         }
 
         // Revert sender. It might need to be restored first if it was pruned.
@@ -709,7 +2258,7 @@ impl Accounts {
 
             // Store sender.
             // Reverting a zero-fee signaling transaction can create a prunable account.
-            self.put_or_prune(txn, sender_address, sender_account);
+            self.put_or_prune(txn, sender_address, sender_account)?;
         }
 
         Ok(())
@@ -745,7 +2294,7 @@ impl Accounts {
         )?;
 
         // Reverting a zero-fee signaling transaction can create a prunable account.
-        self.put_or_prune(txn, sender_address, sender_account);
+        self.put_or_prune(txn, sender_address, sender_account)?;
 
         Ok(())
     }
@@ -781,7 +2330,7 @@ impl Accounts {
         )?;
 
         // The account might have been created by the inherent (i.e. reward inherent).
-        self.put_or_prune(txn, address, account);
+        self.put_or_prune(txn, address, account)?;
 
         Ok(())
     }
@@ -839,4 +2388,52 @@ impl Accounts {
             None => self.tree.get_proof(&self.env.read_transaction(), keys),
         }
     }
+
+    /// Produces a single Merkle proof for the given account keys under the current accounts tree
+    /// root. The proof covers both accounts that exist and accounts that are absent, which can be
+    /// checked with `Accounts::verify_proof`.
+    pub fn prove(
+        &self,
+        keys: &[KeyNibbles],
+        txn_option: Option<&DBTransaction>,
+    ) -> Result<TrieProof, IncompleteTrie> {
+        let keys: BTreeSet<&KeyNibbles> = keys.iter().collect();
+        self.get_proof(txn_option, keys.into_iter().collect())
+    }
+
+    /// Checks a proof produced by `Accounts::prove` against the accounts tree root `root_hash`.
+    /// Returns the account at each of the given keys, or `None` where the proof shows that there
+    /// is no account. Returns `None` if the proof is invalid or doesn't cover all of the keys.
+    pub fn verify_proof(
+        proof: TrieProof,
+        root_hash: &Blake2bHash,
+        keys: &[KeyNibbles],
+    ) -> Option<Vec<Option<Account>>> {
+        let unique_keys: BTreeSet<&KeyNibbles> = keys.iter().collect();
+        let unique_keys: Vec<&KeyNibbles> = unique_keys.into_iter().collect();
+        let values = proof.verify_values(root_hash, &unique_keys).ok()?;
+
+        keys.iter()
+            .map(|key| match values.get(key)? {
+                Some(value) => Account::deserialize_from_vec(value).ok().map(Some),
+                None => Some(None),
+            })
+            .collect()
+    }
+
+    /// Returns the hash of the subtree containing all accounts (and account data) whose keys start
+    /// with `prefix`, or `None` if there are none. Two accounts trees contain the same data below
+    /// `prefix` if and only if their subtree commitments are equal.
+    pub fn subtree_commitment(
+        &self,
+        prefix: &KeyNibbles,
+        txn_option: Option<&DBTransaction>,
+    ) -> Result<Option<Blake2bHash>, IncompleteTrie> {
+        match txn_option {
+            Some(txn) => self.tree.get_subtree_hash(txn, prefix),
+            None => self
+                .tree
+                .get_subtree_hash(&self.env.read_transaction(), prefix),
+        }
+    }
 }
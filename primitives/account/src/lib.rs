@@ -3,11 +3,16 @@
 extern crate log;
 
 #[cfg(feature = "accounts")]
-pub use crate::accounts::{Accounts, AccountsTrie};
+pub use crate::accounts::{
+    AccountChange, Accounts, AccountsTrie, CommitOptions, CommitPacing, CommitStep, DormancyReason,
+    OpLog, StakingSummary, TransactionExplanation, TrieOp,
+};
 #[cfg(feature = "interaction-traits")]
 pub use crate::data_store::{DataStore, DataStoreRead, DataStoreWrite};
 #[cfg(feature = "interaction-traits")]
 pub use crate::interaction_traits::*;
+#[cfg(feature = "accounts")]
+pub use crate::metrics::AccountsMetrics;
 pub use crate::{
     account::{
         basic_account::BasicAccount, htlc_contract::HashedTimeLockedContract, staking_contract::*,
@@ -28,5 +33,9 @@ mod data_store_ops;
 #[cfg(feature = "interaction-traits")]
 mod interaction_traits;
 mod logs;
+#[cfg(feature = "accounts")]
+mod metrics;
 mod receipts;
 mod reserved_balance;
+#[cfg(feature = "accounts")]
+mod validity_cache;
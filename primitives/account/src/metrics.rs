@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use nimiq_trie::IoStats;
+
+/// A snapshot of the counters of an `Accounts`, as returned by `Accounts::metrics`. The counters
+/// are accumulated since the accounts were created and are meant to be exported periodically to
+/// a monitoring system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccountsMetrics {
+    /// The number of node lookups served by the trie node cache. Always zero if the node cache
+    /// is not enabled.
+    pub node_cache_hits: u64,
+    /// The number of node lookups that missed the trie node cache and had to read from the
+    /// database. Always zero if the node cache is not enabled.
+    pub node_cache_misses: u64,
+    /// The number of transactions that `validate_all` skipped because they were already known to
+    /// be valid. Always zero if the validity cache is not enabled.
+    pub validity_cache_hits: u64,
+    /// The number of transactions that `validate_all` had to apply because they weren't known to
+    /// be valid yet. Always zero if the validity cache is not enabled.
+    pub validity_cache_misses: u64,
+    /// The number of blocks whose commit was persisted, as reported by
+    /// `Accounts::count_committed_blocks`.
+    pub commits: u64,
+    /// The number of blocks whose revert was persisted, as reported by
+    /// `Accounts::count_reverted_blocks`.
+    pub reverts: u64,
+    /// The node operations performed by the accounts tree since it was created or
+    /// `Accounts::reset_io_stats` was last called, see `Accounts::io_stats`.
+    pub io: IoStats,
+}
+
+impl AccountsMetrics {
+    /// The fraction of node lookups that were served from the trie node cache, or 0 if there
+    /// were none yet.
+    pub fn node_cache_hit_rate(&self) -> f64 {
+        hit_rate(self.node_cache_hits, self.node_cache_misses)
+    }
+
+    /// The fraction of transactions that `validate_all` found in the validity cache, or 0 if
+    /// there were none yet.
+    pub fn validity_cache_hit_rate(&self) -> f64 {
+        hit_rate(self.validity_cache_hits, self.validity_cache_misses)
+    }
+}
+
+fn hit_rate(hits: u64, misses: u64) -> f64 {
+    let total = hits + misses;
+    if total == 0 {
+        return 0.0;
+    }
+    hits as f64 / total as f64
+}
+
+/// The counters behind `AccountsMetrics::commits` and `AccountsMetrics::reverts`.
+#[derive(Debug, Default)]
+pub(crate) struct BlockCounters {
+    commits: AtomicU64,
+    reverts: AtomicU64,
+}
+
+impl BlockCounters {
+    pub(crate) fn count_commits(&self, count: u64) {
+        self.commits.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_reverts(&self, count: u64) {
+        self.reverts.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn commits(&self) -> u64 {
+        self.commits.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn reverts(&self) -> u64 {
+        self.reverts.load(Ordering::Relaxed)
+    }
+}
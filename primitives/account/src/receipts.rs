@@ -61,19 +61,88 @@ pub enum OperationReceipt<T: Clone + Debug + Serialize + Deserialize> {
 pub type TransactionOperationReceipt = OperationReceipt<TransactionReceipt>;
 pub type InherentOperationReceipt = OperationReceipt<InherentReceipt>;
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Receipts {
     pub transactions: Vec<TransactionOperationReceipt>,
     pub inherents: Vec<InherentOperationReceipt>,
+    /// The version of the account logic that produced these receipts. Receipts stored before
+    /// receipts were versioned are read as version 0.
+    pub version: u8,
+}
+
+impl Receipts {
+    /// The receipt version produced by this version of the account logic. Reverting receipts with
+    /// a newer version fails, since their interpretation is unknown.
+    pub const VERSION: u8 = 1;
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+}
+
+impl Default for Receipts {
+    fn default() -> Self {
+        Receipts {
+            transactions: vec![],
+            inherents: vec![],
+            version: Receipts::VERSION,
+        }
+    }
+}
+
+/// The layout of `Receipts` before receipts were versioned.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ReceiptsV0 {
+    transactions: Vec<TransactionOperationReceipt>,
+    inherents: Vec<InherentOperationReceipt>,
+}
+
+impl From<ReceiptsV0> for Receipts {
+    fn from(receipts: ReceiptsV0) -> Self {
+        Receipts {
+            transactions: receipts.transactions,
+            inherents: receipts.inherents,
+            version: 0,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
-#[repr(u8)]
+#[serde(from = "StoredRevertInfo", into = "StoredRevertInfo")]
 pub enum RevertInfo {
     Receipts(Receipts),
     Diff(TrieDiff),
 }
 
+/// The serialized form of `RevertInfo`. Unversioned receipts keep their original tag, so that
+/// revert info stored by older versions can still be read.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[repr(u8)]
+enum StoredRevertInfo {
+    ReceiptsV0(ReceiptsV0),
+    Diff(TrieDiff),
+    Receipts(Receipts),
+}
+
+impl From<StoredRevertInfo> for RevertInfo {
+    fn from(info: StoredRevertInfo) -> Self {
+        match info {
+            StoredRevertInfo::ReceiptsV0(receipts) => RevertInfo::Receipts(receipts.into()),
+            StoredRevertInfo::Diff(diff) => RevertInfo::Diff(diff),
+            StoredRevertInfo::Receipts(receipts) => RevertInfo::Receipts(receipts),
+        }
+    }
+}
+
+impl From<RevertInfo> for StoredRevertInfo {
+    fn from(info: RevertInfo) -> Self {
+        match info {
+            RevertInfo::Receipts(receipts) => StoredRevertInfo::Receipts(receipts),
+            RevertInfo::Diff(diff) => StoredRevertInfo::Diff(diff),
+        }
+    }
+}
+
 impl From<Receipts> for RevertInfo {
     fn from(receipts: Receipts) -> RevertInfo {
         RevertInfo::Receipts(receipts)
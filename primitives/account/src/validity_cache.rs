@@ -0,0 +1,84 @@
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use nimiq_hash::Blake2bHash;
+use parking_lot::Mutex;
+
+/// Identifies the validation of a transaction in a block: the transaction hash together with the
+/// number and time of the block.
+pub(crate) type ValidityKey = (Blake2bHash, u32, u64);
+
+/// A cache of the transactions that were found to be valid on top of an accounts tree state.
+///
+/// The entries are only valid for the accounts tree root they were validated against. As soon as
+/// the cache is consulted for a different root, i.e. after any account changed, all entries are
+/// dropped. Only successful validations are cached, failing transactions are validated again to
+/// report their error.
+#[derive(Debug)]
+pub(crate) struct ValidityCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    root: Blake2bHash,
+    valid: HashSet<ValidityKey>,
+}
+
+impl ValidityCache {
+    /// Creates an empty cache holding at most `capacity` entries.
+    pub(crate) fn new(capacity: usize) -> Self {
+        ValidityCache {
+            capacity,
+            state: Mutex::new(CacheState::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns whether the transaction was found to be valid on top of the state with the given
+    /// root before.
+    pub(crate) fn is_valid(&self, root: &Blake2bHash, key: &ValidityKey) -> bool {
+        let mut state = self.state.lock();
+        if state.root != *root {
+            state.root = root.clone();
+            state.valid.clear();
+        }
+
+        let valid = state.valid.contains(key);
+        if valid {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        valid
+    }
+
+    /// Records that the transaction is valid on top of the state with the given root.
+    pub(crate) fn insert_valid(&self, root: &Blake2bHash, key: ValidityKey) {
+        let mut state = self.state.lock();
+        if state.root == *root && state.valid.len() < self.capacity {
+            state.valid.insert(key);
+        }
+    }
+
+    /// The number of entries currently held by the cache.
+    pub(crate) fn len(&self) -> usize {
+        self.state.lock().valid.len()
+    }
+
+    /// The number of lookups for which the transaction was known to be valid.
+    pub(crate) fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of lookups for which the transaction wasn't known to be valid.
+    pub(crate) fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
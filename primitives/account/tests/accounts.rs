@@ -1,34 +1,49 @@
-use std::{convert::TryFrom, time::Instant};
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    time::{Duration, Instant},
+};
 
 use log::info;
 use nimiq_account::{
-    Account, Accounts, BasicAccount, BlockLogger, BlockState, InherentOperationReceipt, Log,
-    OperationReceipt, TransactionOperationReceipt, TransactionReceipt, VestingContract,
+    Account, AccountChange, Accounts, BasicAccount, BlockLogger, BlockState, CommitOptions,
+    CommitPacing, CommitStep, DormancyReason, HashedTimeLockedContract, InherentOperationReceipt,
+    Log, OperationReceipt, Receipts, RevertInfo, StakingSummary, TransactionOperationReceipt,
+    TransactionReceipt, TrieOp, VestingContract,
 };
 use nimiq_bls::KeyPair as BLSKeyPair;
 use nimiq_database::{
     mdbx::MdbxDatabase,
     traits::{Database, WriteTransaction},
     volatile::VolatileDatabase,
+    DatabaseProxy,
 };
 use nimiq_genesis_builder::GenesisBuilder;
+use nimiq_hash::Blake2bHash;
 use nimiq_keys::{Address, Ed25519PublicKey, KeyPair, PrivateKey, SecureGenerate};
 use nimiq_primitives::{
-    account::{AccountType, FailReason},
+    account::{AccountError, AccountType, FailReason},
     coin::Coin,
+    key_nibbles::KeyNibbles,
     networks::NetworkId,
     policy::Policy,
     slots_allocation::{JailedValidator, PenalizedSlot},
+    transaction::TransactionError,
+    trie::error::MerkleRadixTrieError,
 };
 use nimiq_serde::{Deserialize, Serialize};
 use nimiq_test_log::test;
 use nimiq_test_utils::{
     accounts_revert::TestCommitRevert,
+    commit_bench::bench_commit,
     test_rng::test_rng,
     test_transaction::{generate_accounts, generate_transactions, TestTransaction},
     transactions::{IncomingType, OutgoingType, TransactionsGenerator, ValidatorState},
 };
-use nimiq_transaction::{inherent::Inherent, SignatureProof, Transaction};
+use nimiq_transaction::{
+    account::htlc_contract::AnyHash, inherent::Inherent, SignatureProof, Transaction,
+};
+use nimiq_vrf::VrfSeed;
 use rand::Rng;
 use tempfile::tempdir;
 
@@ -1082,3 +1097,2507 @@ fn can_revert_inherents() {
         [OperationReceipt::Err(..)]
     ));
 }
+
+#[test]
+fn it_can_list_top_accounts_by_balance() {
+    let address = |i: u8| Address::from([i; Address::SIZE]);
+    let basic = |balance: u64| {
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(balance),
+        })
+    };
+
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (address(1), basic(100)),
+        (address(2), basic(300)),
+        (address(3), basic(200)),
+        (address(4), basic(300)),
+        (address(5), basic(50)),
+    ]);
+
+    // Ties are broken by ascending address.
+    assert_eq!(
+        accounts.top_accounts_by_balance(3, None),
+        vec![
+            (address(2), Coin::from_u64_unchecked(300)),
+            (address(4), Coin::from_u64_unchecked(300)),
+            (address(3), Coin::from_u64_unchecked(200)),
+        ]
+    );
+
+    assert!(accounts.top_accounts_by_balance(0, None).is_empty());
+
+    let all = accounts.top_accounts_by_balance(10, None);
+    assert_eq!(all.len(), 5);
+    assert_eq!(all[4], (address(5), Coin::from_u64_unchecked(50)));
+}
+
+#[test]
+fn it_computes_the_supply_delta_of_a_block() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let address_recipient = Address::from([2u8; Address::SIZE]);
+    let address_validator = Address::from([3u8; Address::SIZE]);
+
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        address_sender.clone(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1000),
+        }),
+    )]);
+
+    let block_number = Policy::blocks_per_batch();
+    let fee = Coin::from_u64_unchecked(10);
+    let block_reward = Coin::from_u64_unchecked(10000);
+
+    let tx = Transaction::new_basic(
+        address_sender,
+        address_recipient,
+        Coin::from_u64_unchecked(100),
+        fee,
+        block_number,
+        NetworkId::Main,
+    );
+
+    // The fees collected in the batch are paid out together with the block reward.
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: address_validator.clone(),
+        value: block_reward + fee,
+    };
+
+    let block_state = BlockState::new(block_number, 1);
+    let root_before = accounts.get_root_hash_assert(None);
+
+    assert_eq!(
+        accounts.supply_delta(&[tx.clone()], &[reward], &block_state),
+        Ok(u64::from(block_reward) as i128)
+    );
+    assert_eq!(
+        accounts.supply_delta(&[tx], &[], &block_state),
+        Ok(-(u64::from(fee) as i128))
+    );
+
+    // The state is left untouched.
+    assert_eq!(accounts.get_root_hash_assert(None), root_before);
+    assert_eq!(
+        accounts.get_complete(&address_validator, None),
+        Account::default()
+    );
+}
+
+#[test]
+fn it_lists_new_addresses_of_a_block() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let address_existing = Address::from([2u8; Address::SIZE]);
+    let address_new = Address::from([3u8; Address::SIZE]);
+    let address_validator = Address::from([4u8; Address::SIZE]);
+
+    let basic = |balance: u64| {
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(balance),
+        })
+    };
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (address_sender.clone(), basic(1000)),
+        (address_existing.clone(), basic(1000)),
+    ]);
+
+    let tx_existing = Transaction::new_basic(
+        address_sender.clone(),
+        address_existing,
+        Coin::from_u64_unchecked(100),
+        Coin::ZERO,
+        1,
+        NetworkId::Main,
+    );
+    let tx_new = Transaction::new_basic(
+        address_sender,
+        address_new.clone(),
+        Coin::from_u64_unchecked(100),
+        Coin::ZERO,
+        1,
+        NetworkId::Main,
+    );
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: address_validator.clone(),
+        value: Coin::from_u64_unchecked(10000),
+    };
+
+    let new_addresses = accounts
+        .new_addresses(&[tx_existing, tx_new], &[reward], &BlockState::new(1, 1))
+        .unwrap();
+    assert_eq!(new_addresses, vec![address_new.clone(), address_validator]);
+
+    // The state is left untouched.
+    assert_eq!(
+        accounts.get_complete(&address_new, None),
+        Account::default()
+    );
+}
+
+#[test]
+fn it_can_undo_a_commit_with_its_oplog() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let address_recipient = Address::from([2u8; Address::SIZE]);
+
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        address_sender.clone(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1000),
+        }),
+    )]);
+    let root_before = accounts.get_root_hash_assert(None);
+
+    let tx = Transaction::new_basic(
+        address_sender,
+        address_recipient.clone(),
+        Coin::from_u64_unchecked(1000),
+        Coin::ZERO,
+        1,
+        NetworkId::Main,
+    );
+
+    let mut raw_txn = accounts.env.write_transaction();
+    let mut txn = (&mut raw_txn).into();
+
+    let (_, oplog) = accounts
+        .commit_with_oplog(
+            &mut txn,
+            &[tx],
+            &[],
+            &BlockState::new(1, 1),
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+
+    // The sender is emptied and thus pruned, the recipient is created.
+    assert_eq!(oplog.0.len(), 2);
+    assert!(matches!(oplog.0[0], TrieOp::Remove(..)));
+    assert!(matches!(oplog.0[1], TrieOp::Put(_, None, _)));
+
+    accounts.apply_oplog_reverse(&mut txn, oplog).unwrap();
+    assert_eq!(accounts.get_root_hash_assert(Some(&txn)), root_before);
+    assert_eq!(
+        accounts.get_complete(&address_recipient, Some(&txn)),
+        Account::default()
+    );
+}
+
+#[test]
+fn it_reports_the_touched_addresses_of_a_commit() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let address_recipient = Address::from([2u8; Address::SIZE]);
+    let address_untouched = Address::from([3u8; Address::SIZE]);
+    let address_rewarded = Address::from([4u8; Address::SIZE]);
+
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (
+            address_sender.clone(),
+            Account::Basic(BasicAccount {
+                balance: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+        (
+            address_untouched.clone(),
+            Account::Basic(BasicAccount {
+                balance: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+    ]);
+
+    let tx = Transaction::new_basic(
+        address_sender.clone(),
+        address_recipient.clone(),
+        Coin::from_u64_unchecked(100),
+        Coin::ZERO,
+        1,
+        NetworkId::Main,
+    );
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: address_rewarded.clone(),
+        value: Coin::from_u64_unchecked(10),
+    };
+
+    let mut raw_txn = accounts.env.write_transaction();
+    let (_, touched) = accounts
+        .commit_with_touched(
+            &mut (&mut raw_txn).into(),
+            &[tx],
+            &[reward],
+            &BlockState::new(1, 1),
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.abort();
+
+    assert_eq!(
+        touched,
+        HashSet::from([address_sender, address_recipient, address_rewarded])
+    );
+}
+
+#[test]
+fn it_computes_storage_by_account_type() {
+    let basic = |balance: u64| {
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(balance),
+        })
+    };
+    let vesting = Account::Vesting(VestingContract {
+        balance: Coin::from_u64_unchecked(1000),
+        owner: Address::from([9u8; Address::SIZE]),
+        start_time: 0,
+        time_step: 100,
+        step_amount: Coin::from_u64_unchecked(100),
+        total_amount: Coin::from_u64_unchecked(1000),
+    });
+
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (Address::from([1u8; Address::SIZE]), basic(100)),
+        (Address::from([2u8; Address::SIZE]), basic(200)),
+        (Address::from([3u8; Address::SIZE]), vesting.clone()),
+    ]);
+
+    let storage = accounts.storage_by_type(None);
+    assert_eq!(storage.len(), 2);
+    assert_eq!(
+        storage[&AccountType::Basic],
+        (basic(100).serialized_size() + basic(200).serialized_size()) as u64
+    );
+    assert_eq!(
+        storage[&AccountType::Vesting],
+        vesting.serialized_size() as u64
+    );
+}
+
+#[test]
+fn it_validates_transactions_independently() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let address_recipient = Address::from([2u8; Address::SIZE]);
+
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        address_sender.clone(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1000),
+        }),
+    )]);
+
+    let tx = |value: u64| {
+        Transaction::new_basic(
+            address_sender.clone(),
+            address_recipient.clone(),
+            Coin::from_u64_unchecked(value),
+            Coin::ZERO,
+            1,
+            NetworkId::Main,
+        )
+    };
+
+    // Each transaction is validated against the current state, so both 800 Luna transactions
+    // are valid on their own.
+    let failures = accounts.validate_all(&[tx(800), tx(2000), tx(800)], &BlockState::new(1, 1));
+    assert_eq!(
+        failures,
+        vec![(
+            1,
+            AccountError::InsufficientFunds {
+                needed: Coin::from_u64_unchecked(2000),
+                balance: Coin::from_u64_unchecked(1000),
+            }
+        )]
+    );
+}
+
+#[test]
+fn it_packs_blocks_by_fee_per_byte() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let address_recipient = Address::from([2u8; Address::SIZE]);
+
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        address_sender.clone(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1000),
+        }),
+    )]);
+
+    let tx = |value: u64, fee: u64| {
+        Transaction::new_basic(
+            address_sender.clone(),
+            address_recipient.clone(),
+            Coin::from_u64_unchecked(value),
+            Coin::from_u64_unchecked(fee),
+            1,
+            NetworkId::Main,
+        )
+    };
+
+    let low_fee = tx(100, 1);
+    let high_fee = tx(500, 50);
+    let unaffordable = tx(500, 40);
+    let block_state = BlockState::new(1, 1);
+    let pending = [low_fee.clone(), unaffordable, high_fee.clone()];
+
+    // The second most profitable transaction doesn't apply after the most profitable one.
+    assert_eq!(
+        accounts.pack_block(&pending, usize::MAX, &block_state),
+        vec![high_fee.clone(), low_fee]
+    );
+
+    // Only one transaction fits into the size limit.
+    assert_eq!(
+        accounts.pack_block(&pending, high_fee.serialized_size(), &block_state),
+        vec![high_fee]
+    );
+}
+
+#[test]
+fn it_rejects_transactions_for_other_networks() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone()).with_network_id(NetworkId::TestAlbatross);
+
+    let address = Address::from([1u8; Address::SIZE]);
+    let tx = Transaction::new_basic(
+        address.clone(),
+        address,
+        Coin::from_u64_unchecked(10),
+        Coin::ZERO,
+        1,
+        NetworkId::Main,
+    );
+
+    let mut raw_txn = env.write_transaction();
+    let result = accounts.commit(
+        &mut (&mut raw_txn).into(),
+        &[tx],
+        &[],
+        &BlockState::new(1, 1),
+        &mut BlockLogger::empty(),
+    );
+
+    assert_eq!(
+        result.err(),
+        Some(AccountError::WrongNetwork {
+            expected: NetworkId::TestAlbatross,
+            got: NetworkId::Main,
+            index: 0,
+        })
+    );
+}
+
+#[test]
+fn it_reports_the_transaction_that_made_a_commit_fail() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone()).with_network_id(NetworkId::TestAlbatross);
+
+    let address = Address::from([1u8; Address::SIZE]);
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: address.clone(),
+        value: Coin::from_u64_unchecked(1000),
+    };
+    let mut raw_txn = env.write_transaction();
+    accounts
+        .commit_checked(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward],
+            &BlockState::new(1, 1),
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+
+    let new_transaction = |network_id| {
+        Transaction::new_basic(
+            address.clone(),
+            address.clone(),
+            Coin::from_u64_unchecked(10),
+            Coin::ZERO,
+            1,
+            network_id,
+        )
+    };
+    let transactions = [
+        new_transaction(NetworkId::TestAlbatross),
+        new_transaction(NetworkId::Main),
+    ];
+
+    let mut raw_txn = env.write_transaction();
+    let result = accounts.commit_checked(
+        &mut (&mut raw_txn).into(),
+        &transactions,
+        &[],
+        &BlockState::new(2, 2),
+        &mut BlockLogger::empty(),
+    );
+    raw_txn.abort();
+
+    assert_eq!(
+        result.unwrap_err(),
+        (
+            CommitStep::Transaction(1),
+            AccountError::WrongNetwork {
+                expected: NetworkId::TestAlbatross,
+                got: NetworkId::Main,
+                index: 1,
+            }
+        )
+    );
+}
+
+#[test]
+fn it_commits_blocks_without_updating_the_root() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone()).with_network_id(NetworkId::TestAlbatross);
+
+    let address = Address::from([1u8; Address::SIZE]);
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: address.clone(),
+        value: Coin::from_u64_unchecked(1000),
+    };
+    let mut raw_txn = env.write_transaction();
+    accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward.clone()],
+            &BlockState::new(1, 1),
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+    let root = accounts.get_root_hash_assert(None);
+
+    let new_transaction = |value, network_id| {
+        Transaction::new_basic(
+            address.clone(),
+            Address::from([2u8; Address::SIZE]),
+            Coin::from_u64_unchecked(value),
+            Coin::ZERO,
+            1,
+            network_id,
+        )
+    };
+    let block_state = BlockState::new(2, 2);
+
+    // A failing transaction doesn't make the block invalid.
+    let transactions = [
+        new_transaction(10, NetworkId::TestAlbatross),
+        new_transaction(5000, NetworkId::TestAlbatross),
+    ];
+    assert_eq!(
+        accounts.commit_without_root_update(&transactions, &[reward.clone()], &block_state),
+        Ok(())
+    );
+
+    let transactions = [new_transaction(10, NetworkId::Main)];
+    assert_eq!(
+        accounts.commit_without_root_update(&transactions, &[reward], &block_state),
+        Err(AccountError::WrongNetwork {
+            expected: NetworkId::TestAlbatross,
+            got: NetworkId::Main,
+            index: 0,
+        })
+    );
+
+    assert_eq!(accounts.get_root_hash_assert(None), root);
+}
+
+#[test]
+fn it_computes_remaining_vesting_time() {
+    let vesting_address = Address::from([1u8; Address::SIZE]);
+    let basic_address = Address::from([2u8; Address::SIZE]);
+
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (
+            vesting_address.clone(),
+            Account::Vesting(VestingContract {
+                balance: Coin::from_u64_unchecked(1000),
+                owner: Address::from([9u8; Address::SIZE]),
+                start_time: 1000,
+                time_step: 100,
+                step_amount: Coin::from_u64_unchecked(300),
+                total_amount: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+        (
+            basic_address.clone(),
+            Account::Basic(BasicAccount {
+                balance: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+    ]);
+
+    // Four steps of 300 are needed to release 1000, so the contract is fully vested at 1400.
+    assert_eq!(
+        accounts.vesting_remaining_time(&vesting_address, 0, None),
+        Some(1400)
+    );
+    assert_eq!(
+        accounts.vesting_remaining_time(&vesting_address, 1350, None),
+        Some(50)
+    );
+    assert_eq!(
+        accounts.vesting_remaining_time(&vesting_address, 1400, None),
+        Some(0)
+    );
+    assert_eq!(
+        accounts.vesting_remaining_time(&vesting_address, 5000, None),
+        Some(0)
+    );
+
+    assert_eq!(
+        accounts.vesting_remaining_time(&basic_address, 0, None),
+        None
+    );
+    assert_eq!(
+        accounts.vesting_remaining_time(&Address::from([3u8; Address::SIZE]), 0, None),
+        None
+    );
+}
+
+#[test]
+fn it_keeps_the_roots_of_recent_blocks() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone()).with_root_history(2);
+
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: Address::from([1u8; Address::SIZE]),
+        value: Coin::from_u64_unchecked(10000),
+    };
+
+    let mut receipts = vec![];
+    let mut roots = vec![];
+    for block_number in 1..=3 {
+        let mut raw_txn = env.write_transaction();
+        let block_receipts = accounts
+            .commit(
+                &mut (&mut raw_txn).into(),
+                &[],
+                &[reward.clone()],
+                &BlockState::new(block_number, 1),
+                &mut BlockLogger::empty(),
+            )
+            .unwrap();
+        raw_txn.commit();
+
+        receipts.push(block_receipts);
+        roots.push(accounts.get_root_hash_assert(None));
+    }
+
+    // Only the last two blocks are kept.
+    assert_eq!(accounts.recent_root(1, None), None);
+    assert_eq!(accounts.recent_root(2, None), Some(roots[1].clone()));
+    assert_eq!(accounts.recent_root(3, None), Some(roots[2].clone()));
+
+    // Reverting a block removes its root.
+    let mut raw_txn = env.write_transaction();
+    accounts
+        .revert(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward],
+            &BlockState::new(3, 1),
+            receipts.pop().unwrap().into(),
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+
+    assert_eq!(accounts.recent_root(2, None), Some(roots[1].clone()));
+    assert_eq!(accounts.recent_root(3, None), None);
+    assert_eq!(accounts.get_root_hash_assert(None), roots[1]);
+}
+
+#[test]
+fn it_measures_commit_throughput() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+    let mut rng = test_rng(true);
+
+    let result = bench_commit(&accounts, 10, &mut rng);
+    assert_eq!(result.num_transactions, 10);
+    assert!(result.transactions_per_second > 0.0);
+    assert!(result.bytes_written > 0);
+
+    // The transfers are discarded, only the funded senders remain.
+    assert_eq!(accounts.size(), 10);
+}
+
+#[test]
+fn it_simulates_contract_transactions() {
+    let key_pair = KeyPair::generate(&mut test_rng(true));
+    let contract_address = Address::from([1u8; Address::SIZE]);
+    let contract = VestingContract {
+        balance: Coin::from_u64_unchecked(1000),
+        owner: Address::from(&key_pair.public),
+        start_time: 0,
+        time_step: 100,
+        step_amount: Coin::from_u64_unchecked(100),
+        total_amount: Coin::from_u64_unchecked(1000),
+    };
+
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        contract_address.clone(),
+        Account::Vesting(contract.clone()),
+    )]);
+
+    let signed_tx = |value: u64| {
+        let mut tx = Transaction::new_basic(
+            contract_address.clone(),
+            Address::from([2u8; Address::SIZE]),
+            Coin::from_u64_unchecked(value),
+            Coin::from_u64_unchecked(10),
+            1,
+            NetworkId::Dummy,
+        );
+        tx.sender_type = AccountType::Vesting;
+        let signature = key_pair.sign(&tx.serialize_content()[..]);
+        tx.proof = SignatureProof::from_ed25519(key_pair.public, signature).serialize_to_vec();
+        tx
+    };
+
+    // At time 300, 300 coins are released.
+    let block_state = BlockState::new(1, 300);
+    assert_eq!(
+        accounts.simulate_contract_tx(&signed_tx(200), &block_state),
+        Ok(Account::Vesting(VestingContract {
+            balance: Coin::from_u64_unchecked(790),
+            ..contract.clone()
+        }))
+    );
+    assert!(matches!(
+        accounts.simulate_contract_tx(&signed_tx(500), &block_state),
+        Err(AccountError::InsufficientFunds { .. })
+    ));
+
+    // The state is left untouched.
+    assert_eq!(
+        accounts.get_complete(&contract_address, None),
+        Account::Vesting(contract)
+    );
+}
+
+#[test]
+fn it_verifies_reverts_against_a_prior_root() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+    let prior_root = accounts.get_root_hash_assert(None);
+
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: Address::from([1u8; Address::SIZE]),
+        value: Coin::from_u64_unchecked(10000),
+    };
+    let block_state = BlockState::new(1, 1);
+
+    let mut raw_txn = env.write_transaction();
+    let receipts = accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward.clone()],
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+    let root = accounts.get_root_hash_assert(None);
+
+    assert_eq!(
+        accounts.verify_revert_to(
+            &[],
+            &[reward.clone()],
+            &block_state,
+            receipts.clone().into(),
+            &prior_root
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        accounts.verify_revert_to(&[], &[reward], &block_state, receipts.into(), &root),
+        Err(AccountError::RootMismatch {
+            expected: root.clone(),
+            got: prior_root,
+        })
+    );
+
+    // The state is left untouched.
+    assert_eq!(accounts.get_root_hash_assert(None), root);
+}
+
+#[test]
+fn it_reverts_a_single_transaction() {
+    let sender = Address::from([1u8; Address::SIZE]);
+    let recipient = Address::from([2u8; Address::SIZE]);
+    let new_transaction = |value| {
+        Transaction::new_basic(
+            sender.clone(),
+            recipient.clone(),
+            Coin::from_u64_unchecked(value),
+            Coin::from_u64_unchecked(1),
+            1,
+            NetworkId::UnitAlbatross,
+        )
+    };
+    let transactions = [new_transaction(100), new_transaction(200)];
+    let block_state = BlockState::new(2, 2);
+
+    // Commits the given transactions on top of a funded sender and returns the receipts.
+    let commit = |accounts: &Accounts, env: &DatabaseProxy, transactions: &[Transaction]| {
+        let reward = Inherent::Reward {
+            validator_address: Address::burn_address(),
+            target: sender.clone(),
+            value: Coin::from_u64_unchecked(1000),
+        };
+        let mut raw_txn = env.write_transaction();
+        let mut txn = (&mut raw_txn).into();
+        accounts
+            .commit(
+                &mut txn,
+                &[],
+                &[reward],
+                &BlockState::new(1, 1),
+                &mut BlockLogger::empty(),
+            )
+            .unwrap();
+        let receipts = accounts
+            .commit(
+                &mut txn,
+                transactions,
+                &[],
+                &block_state,
+                &mut BlockLogger::empty(),
+            )
+            .unwrap();
+        drop(txn);
+        raw_txn.commit();
+        receipts
+    };
+
+    let env_expected = VolatileDatabase::new(20).unwrap();
+    let accounts_expected = Accounts::new(env_expected.clone());
+    commit(&accounts_expected, &env_expected, &transactions[..1]);
+
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+    let receipts = commit(&accounts, &env, &transactions);
+
+    let mut raw_txn = env.write_transaction();
+    let root = accounts
+        .revert_single(
+            &mut (&mut raw_txn).into(),
+            &transactions[1],
+            &block_state,
+            receipts.transactions[1].clone(),
+        )
+        .unwrap();
+    raw_txn.commit();
+
+    assert_eq!(root, accounts_expected.get_root_hash_assert(None));
+    assert_eq!(root, accounts.get_root_hash_assert(None));
+
+    // Reverting a transaction whose recipient type doesn't match fails.
+    let mut transaction = transactions[0].clone();
+    transaction.recipient_type = AccountType::Vesting;
+    let mut raw_txn = env.write_transaction();
+    let result = accounts.revert_single(
+        &mut (&mut raw_txn).into(),
+        &transaction,
+        &block_state,
+        receipts.transactions[0].clone(),
+    );
+    raw_txn.abort();
+    assert_eq!(
+        result.unwrap_err(),
+        AccountError::TypeMismatch {
+            expected: AccountType::Vesting,
+            got: AccountType::Basic,
+        }
+    );
+}
+
+#[test]
+fn it_checks_the_recipient_type_on_revert() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+
+    let sender = Address::from([1u8; Address::SIZE]);
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: sender.clone(),
+        value: Coin::from_u64_unchecked(1000),
+    };
+    let mut transaction = Transaction::new_basic(
+        sender,
+        Address::from([2u8; Address::SIZE]),
+        Coin::from_u64_unchecked(100),
+        Coin::from_u64_unchecked(1),
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    let block_state = BlockState::new(1, 1);
+
+    let mut raw_txn = env.write_transaction();
+    let receipts = accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[transaction.clone()],
+            &[reward.clone()],
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+
+    // The recipient is a basic account, so reverting a transaction to a vesting contract fails.
+    transaction.recipient_type = AccountType::Vesting;
+    let mut raw_txn = env.write_transaction();
+    let result = accounts.revert(
+        &mut (&mut raw_txn).into(),
+        &[transaction],
+        &[reward],
+        &block_state,
+        receipts.into(),
+        &mut BlockLogger::empty(),
+    );
+    raw_txn.abort();
+    assert_eq!(
+        result.unwrap_err(),
+        AccountError::TypeMismatch {
+            expected: AccountType::Vesting,
+            got: AccountType::Basic,
+        }
+    );
+}
+
+#[test]
+fn it_restores_a_funded_address_when_reverting_contract_creation() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+
+    let owner = Address::from([1u8; Address::SIZE]);
+    let mut data = owner.serialize_to_vec();
+    data.extend(1000u64.to_be_bytes().serialize_to_vec());
+    let transaction = Transaction::new_contract_creation(
+        owner.clone(),
+        AccountType::Basic,
+        vec![],
+        AccountType::Vesting,
+        data,
+        Coin::from_u64_unchecked(100),
+        Coin::ZERO,
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    let contract_address = transaction.contract_creation_address();
+
+    // Fund both the owner and the address the contract is going to be created at.
+    let rewards = [
+        Inherent::Reward {
+            validator_address: Address::burn_address(),
+            target: owner,
+            value: Coin::from_u64_unchecked(1000),
+        },
+        Inherent::Reward {
+            validator_address: Address::burn_address(),
+            target: contract_address.clone(),
+            value: Coin::from_u64_unchecked(50),
+        },
+    ];
+    let mut raw_txn = env.write_transaction();
+    accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &rewards,
+            &BlockState::new(1, 1),
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+    let funded_root = accounts.get_root_hash_assert(None);
+
+    let block_state = BlockState::new(2, 2);
+    let mut raw_txn = env.write_transaction();
+    let receipts = accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[transaction.clone()],
+            &[],
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+
+    match accounts.get_complete(&contract_address, None) {
+        Account::Vesting(contract) => {
+            assert_eq!(contract.balance, Coin::from_u64_unchecked(150))
+        }
+        account => panic!("Expected a vesting contract, got {:?}", account),
+    }
+
+    let mut raw_txn = env.write_transaction();
+    accounts
+        .revert(
+            &mut (&mut raw_txn).into(),
+            &[transaction],
+            &[],
+            &block_state,
+            receipts.into(),
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+
+    assert_eq!(
+        accounts.get_complete(&contract_address, None),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(50)
+        })
+    );
+    assert_eq!(accounts.get_root_hash_assert(None), funded_root);
+}
+
+#[test]
+fn it_reads_unversioned_receipts() {
+    /// The layout of `RevertInfo` and `Receipts` before receipts were versioned.
+    #[derive(Serialize)]
+    #[repr(u8)]
+    enum RevertInfoV0 {
+        Receipts(ReceiptsV0),
+    }
+
+    #[derive(Serialize)]
+    struct ReceiptsV0 {
+        transactions: Vec<TransactionOperationReceipt>,
+        inherents: Vec<InherentOperationReceipt>,
+    }
+
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: Address::from([1u8; Address::SIZE]),
+        value: Coin::from_u64_unchecked(1000),
+    };
+    let block_state = BlockState::new(1, 1);
+    let initial_root = accounts.get_root_hash_assert(None);
+
+    let mut raw_txn = env.write_transaction();
+    let receipts = accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward.clone()],
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+    assert_eq!(receipts.version(), Receipts::VERSION);
+
+    // Versioned receipts survive a round trip.
+    let revert_info = RevertInfo::from(receipts.clone());
+    match RevertInfo::deserialize_from_vec(&revert_info.serialize_to_vec()).unwrap() {
+        RevertInfo::Receipts(receipts) => assert_eq!(receipts.version(), Receipts::VERSION),
+        RevertInfo::Diff(_) => panic!("Expected receipts"),
+    }
+
+    // Receipts newer than the account logic are rejected.
+    let mut newer_receipts = receipts.clone();
+    newer_receipts.version = Receipts::VERSION + 1;
+    let mut raw_txn = env.write_transaction();
+    let result = accounts.revert(
+        &mut (&mut raw_txn).into(),
+        &[],
+        &[reward.clone()],
+        &block_state,
+        newer_receipts.into(),
+        &mut BlockLogger::empty(),
+    );
+    raw_txn.abort();
+    assert_eq!(
+        result.unwrap_err(),
+        AccountError::UnsupportedReceiptVersion {
+            version: Receipts::VERSION + 1,
+            supported: Receipts::VERSION,
+        }
+    );
+
+    // Unversioned receipts are read as version 0 and can still be reverted.
+    let blob = RevertInfoV0::Receipts(ReceiptsV0 {
+        transactions: receipts.transactions,
+        inherents: receipts.inherents,
+    })
+    .serialize_to_vec();
+    let revert_info = RevertInfo::deserialize_from_vec(&blob).unwrap();
+    match &revert_info {
+        RevertInfo::Receipts(receipts) => assert_eq!(receipts.version(), 0),
+        RevertInfo::Diff(_) => panic!("Expected receipts"),
+    }
+
+    let mut raw_txn = env.write_transaction();
+    accounts
+        .revert(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward],
+            &block_state,
+            revert_info,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+    assert_eq!(accounts.get_root_hash_assert(None), initial_root);
+}
+
+#[test]
+fn it_commits_only_if_the_root_matches() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+    let prior_root = accounts.get_root_hash_assert(None);
+
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: Address::from([1u8; Address::SIZE]),
+        value: Coin::from_u64_unchecked(10000),
+    };
+    let block_state = BlockState::new(1, 1);
+
+    // Compute the root after the block without persisting it.
+    let mut raw_txn = env.write_transaction();
+    accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward.clone()],
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    let root = accounts.get_root_hash_assert(Some(&raw_txn));
+    raw_txn.abort();
+
+    let result = accounts.commit_if_root(&[], &[reward.clone()], &block_state, &prior_root);
+    assert_eq!(
+        result.unwrap_err(),
+        AccountError::RootMismatch {
+            expected: prior_root.clone(),
+            got: root.clone(),
+        }
+    );
+    assert_eq!(accounts.get_root_hash_assert(None), prior_root);
+
+    let receipts = accounts
+        .commit_if_root(&[], &[reward], &block_state, &root)
+        .unwrap();
+    assert_eq!(receipts.inherents.len(), 1);
+    assert_eq!(accounts.get_root_hash_assert(None), root);
+}
+
+#[test]
+fn it_paces_commits_without_changing_the_root() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let address_recipient = Address::from([2u8; Address::SIZE]);
+
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        address_sender.clone(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1000),
+        }),
+    )]);
+
+    let transactions: Vec<_> = (1..=3)
+        .map(|validity_start_height| {
+            Transaction::new_basic(
+                address_sender.clone(),
+                address_recipient.clone(),
+                Coin::from_u64_unchecked(100),
+                Coin::ZERO,
+                validity_start_height,
+                NetworkId::Main,
+            )
+        })
+        .collect();
+    let block_state = BlockState::new(1, 1);
+
+    let mut raw_txn = accounts.env.write_transaction();
+    let mut txn = (&mut raw_txn).into();
+    let receipts = accounts
+        .commit(
+            &mut txn,
+            &transactions,
+            &[],
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    let root = accounts.get_root_hash_assert(Some(&txn));
+    raw_txn.abort();
+
+    // Only allow the size of ten transactions per second, so committing all three transactions
+    // takes about 300ms.
+    let mut pacing = CommitPacing::new(10 * transactions[0].serialized_size() as u64);
+    assert_eq!(pacing.delay(), Duration::ZERO);
+
+    let mut raw_txn = accounts.env.write_transaction();
+    let mut txn = (&mut raw_txn).into();
+    let start = Instant::now();
+    let paced_receipts = accounts
+        .commit_paced(
+            &mut txn,
+            &transactions,
+            &[],
+            &block_state,
+            &mut BlockLogger::empty(),
+            Some(&mut pacing),
+        )
+        .unwrap();
+
+    // The commit waits between the transactions, so the rate is not exceeded afterwards.
+    assert!(start.elapsed() >= Duration::from_millis(250));
+    assert_eq!(pacing.delay(), Duration::ZERO);
+    assert_eq!(paced_receipts.transactions, receipts.transactions);
+    assert_eq!(accounts.get_root_hash_assert(Some(&txn)), root);
+}
+
+#[test]
+fn it_previews_reverts_with_a_dry_run() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+    let prior_root = accounts.get_root_hash_assert(None);
+
+    let rewards: Vec<_> = [1u8, 2, 1]
+        .iter()
+        .map(|i| Inherent::Reward {
+            validator_address: Address::burn_address(),
+            target: Address::from([*i; Address::SIZE]),
+            value: Coin::from_u64_unchecked(10000),
+        })
+        .collect();
+    let block_state = BlockState::new(1, 1);
+
+    let mut raw_txn = env.write_transaction();
+    let receipts = accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &rewards,
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+    let root = accounts.get_root_hash_assert(None);
+
+    let (reverted_root, addresses) = accounts
+        .revert_dry_run(&[], &rewards, &block_state, receipts.into())
+        .unwrap();
+    assert_eq!(reverted_root, prior_root);
+    assert_eq!(
+        addresses,
+        vec![
+            Address::from([1u8; Address::SIZE]),
+            Address::from([2u8; Address::SIZE])
+        ]
+    );
+
+    // The state is left untouched.
+    assert_eq!(accounts.get_root_hash_assert(None), root);
+}
+
+#[test]
+fn it_verifies_account_invariants() {
+    let basic_address = Address::from([1u8; Address::SIZE]);
+    let empty_address = Address::from([2u8; Address::SIZE]);
+    let htlc_address = Address::from([3u8; Address::SIZE]);
+    let missing_address = Address::from([4u8; Address::SIZE]);
+    let vesting_address = Address::from([5u8; Address::SIZE]);
+
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (
+            basic_address.clone(),
+            Account::Basic(BasicAccount {
+                balance: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+        (
+            empty_address.clone(),
+            Account::Basic(BasicAccount {
+                balance: Coin::ZERO,
+            }),
+        ),
+        (
+            htlc_address.clone(),
+            Account::HTLC(HashedTimeLockedContract {
+                balance: Coin::from_u64_unchecked(1000),
+                sender: Address::from([8u8; Address::SIZE]),
+                recipient: Address::from([9u8; Address::SIZE]),
+                hash_root: AnyHash::default(),
+                hash_count: 0,
+                timeout: 1200,
+                total_amount: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+        (
+            vesting_address.clone(),
+            Account::Vesting(VestingContract {
+                balance: Coin::from_u64_unchecked(1000),
+                owner: Address::from([9u8; Address::SIZE]),
+                start_time: 0,
+                time_step: 100,
+                step_amount: Coin::from_u64_unchecked(100),
+                total_amount: Coin::from_u64_unchecked(500),
+            }),
+        ),
+    ]);
+
+    assert_eq!(
+        accounts.verify_invariants(&[basic_address.clone(), missing_address], None),
+        Ok(())
+    );
+    assert_eq!(
+        accounts.verify_invariants(&[basic_address.clone(), empty_address.clone()], None),
+        Err(AccountError::InvariantViolation {
+            address: empty_address,
+            invariant: "account is not pruned",
+        })
+    );
+    assert_eq!(
+        accounts.verify_invariants(&[htlc_address.clone()], None),
+        Err(AccountError::InvariantViolation {
+            address: htlc_address,
+            invariant: "hash count is zero",
+        })
+    );
+    assert_eq!(
+        accounts.verify_invariants(&[vesting_address.clone()], None),
+        Err(AccountError::InvariantViolation {
+            address: vesting_address,
+            invariant: "balance exceeds the total amount",
+        })
+    );
+    assert_eq!(
+        accounts.verify_invariants(&[Policy::STAKING_CONTRACT_ADDRESS], None),
+        Err(AccountError::InvariantViolation {
+            address: Policy::STAKING_CONTRACT_ADDRESS,
+            invariant: "staking contract is missing",
+        })
+    );
+}
+
+#[test]
+fn it_returns_accounts_with_their_depth() {
+    let shallow_address = Address::from([0x10u8; Address::SIZE]);
+    let mut deep_address_1 = Address::from([0x20u8; Address::SIZE]);
+    deep_address_1.0[19] = 0x21;
+    let mut deep_address_2 = Address::from([0x20u8; Address::SIZE]);
+    deep_address_2.0[19] = 0x22;
+
+    let basic = |balance| {
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(balance),
+        })
+    };
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (shallow_address.clone(), basic(1)),
+        (deep_address_1.clone(), basic(2)),
+        (deep_address_2.clone(), basic(3)),
+    ]);
+
+    // The deep addresses share all but their last nibble, so they are placed below a common
+    // branch node.
+    assert_eq!(
+        accounts.get_with_depth(&shallow_address, None),
+        Ok(Some((basic(1), 1)))
+    );
+    assert_eq!(
+        accounts.get_with_depth(&deep_address_2, None),
+        Ok(Some((basic(3), 2)))
+    );
+    assert_eq!(
+        accounts.get_with_depth(&Address::from([0x30u8; Address::SIZE]), None),
+        Ok(None)
+    );
+}
+
+#[test]
+fn it_gets_many_accounts() {
+    let address_1 = Address::from([1u8; Address::SIZE]);
+    let address_2 = Address::from([2u8; Address::SIZE]);
+    let missing_address = Address::from([3u8; Address::SIZE]);
+
+    let basic = |balance| {
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(balance),
+        })
+    };
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (address_1.clone(), basic(1)),
+        (address_2.clone(), basic(2)),
+    ]);
+
+    let keys: Vec<KeyNibbles> = [&address_2, &missing_address, &address_1, &address_2]
+        .into_iter()
+        .map(KeyNibbles::from)
+        .collect();
+    assert_eq!(
+        accounts.get_many(&keys, None),
+        Ok(vec![Some(basic(2)), None, Some(basic(1)), Some(basic(2))])
+    );
+}
+
+#[test]
+fn it_proves_the_presence_and_absence_of_accounts() {
+    let address_1 = Address::from([1u8; Address::SIZE]);
+    let address_2 = Address::from([2u8; Address::SIZE]);
+    let missing_address = Address::from([3u8; Address::SIZE]);
+
+    let basic = |balance| {
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(balance),
+        })
+    };
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (address_1.clone(), basic(1)),
+        (address_2.clone(), basic(2)),
+    ]);
+    let root = accounts.get_root_hash_assert(None);
+
+    let prove = |addresses: &[&Address]| {
+        let keys: Vec<KeyNibbles> = addresses.iter().map(|&a| KeyNibbles::from(a)).collect();
+        let proof = accounts.prove(&keys, None).unwrap();
+        Accounts::verify_proof(proof, &root, &keys)
+    };
+
+    // A present account.
+    assert_eq!(prove(&[&address_1]), Some(vec![Some(basic(1))]));
+
+    // An absent account.
+    assert_eq!(prove(&[&missing_address]), Some(vec![None]));
+
+    // A batch of mixed keys.
+    assert_eq!(
+        prove(&[&address_2, &missing_address, &address_1, &address_2]),
+        Some(vec![Some(basic(2)), None, Some(basic(1)), Some(basic(2))])
+    );
+
+    // A proof doesn't verify against another root or for keys it doesn't cover.
+    let keys = [KeyNibbles::from(&address_1)];
+    let proof = accounts.prove(&keys, None).unwrap();
+    assert_eq!(
+        Accounts::verify_proof(proof.clone(), &Blake2bHash::default(), &keys),
+        None
+    );
+    assert_eq!(
+        Accounts::verify_proof(proof, &root, &[KeyNibbles::from(&address_2)]),
+        None
+    );
+}
+
+#[test]
+fn it_iterates_over_all_accounts_in_key_order() {
+    let basic = |balance| {
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(balance),
+        })
+    };
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (Address::from([3u8; Address::SIZE]), basic(3)),
+        (Address::from([1u8; Address::SIZE]), basic(1)),
+        (Address::from([2u8; Address::SIZE]), basic(2)),
+    ]);
+
+    let txn = accounts.env.read_transaction();
+    let items: Vec<(KeyNibbles, Account)> = accounts.iter(&txn).collect();
+    assert_eq!(
+        items,
+        (1..=3)
+            .map(|i| (
+                KeyNibbles::from(&Address::from([i; Address::SIZE])),
+                basic(i as u64)
+            ))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn it_caches_valid_transactions() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone()).with_validity_cache(10);
+    let sender = Address::from([1u8; Address::SIZE]);
+
+    let fund_sender = |value| {
+        let reward = Inherent::Reward {
+            validator_address: Address::burn_address(),
+            target: sender.clone(),
+            value: Coin::from_u64_unchecked(value),
+        };
+        let mut raw_txn = env.write_transaction();
+        accounts
+            .commit(
+                &mut (&mut raw_txn).into(),
+                &[],
+                &[reward],
+                &BlockState::new(1, 1),
+                &mut BlockLogger::empty(),
+            )
+            .unwrap();
+        raw_txn.commit();
+    };
+    let new_transaction = |value| {
+        Transaction::new_basic(
+            sender.clone(),
+            Address::from([2u8; Address::SIZE]),
+            Coin::from_u64_unchecked(value),
+            Coin::ZERO,
+            1,
+            NetworkId::UnitAlbatross,
+        )
+    };
+
+    fund_sender(1000);
+    let transactions = [new_transaction(500), new_transaction(2000)];
+    let block_state = BlockState::new(2, 2);
+
+    // Only the valid transaction is cached.
+    let failures = accounts.validate_all(&transactions, &block_state);
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0, 1);
+    assert_eq!(accounts.validity_cache_len(), Some(1));
+
+    // The invalid transaction is validated again and still fails.
+    let failures = accounts.validate_all(&transactions, &block_state);
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].0, 1);
+    assert_eq!(accounts.validity_cache_len(), Some(1));
+
+    // Once the state changes, the cached results are dropped.
+    fund_sender(1000);
+    assert!(accounts
+        .validate_all(&transactions, &block_state)
+        .is_empty());
+    assert_eq!(accounts.validity_cache_len(), Some(2));
+}
+
+#[test]
+fn it_reports_metrics() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::with_node_cache(env.clone(), 100).with_validity_cache(10);
+
+    let sender = Address::from([1u8; Address::SIZE]);
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: sender.clone(),
+        value: Coin::from_u64_unchecked(1000),
+    };
+    let block_state = BlockState::new(1, 1);
+
+    let mut raw_txn = env.write_transaction();
+    let receipts = accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward.clone()],
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+    accounts.count_committed_blocks(1);
+
+    // The first validation misses the validity cache, the second one hits it.
+    let transaction = Transaction::new_basic(
+        sender.clone(),
+        Address::from([2u8; Address::SIZE]),
+        Coin::from_u64_unchecked(500),
+        Coin::ZERO,
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    let next_block_state = BlockState::new(2, 2);
+    assert!(accounts
+        .validate_all(&[transaction.clone()], &next_block_state)
+        .is_empty());
+    assert!(accounts
+        .validate_all(&[transaction], &next_block_state)
+        .is_empty());
+
+    accounts.get(&sender, None).unwrap();
+    accounts.get(&sender, None).unwrap();
+
+    let mut raw_txn = env.write_transaction();
+    accounts
+        .revert(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward.clone()],
+            &block_state,
+            receipts.into(),
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.commit();
+    accounts.count_reverted_blocks(1);
+
+    // A commit in a write transaction that is aborted isn't counted.
+    let mut raw_txn = env.write_transaction();
+    accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &[],
+            &[reward],
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    raw_txn.abort();
+
+    let metrics = accounts.metrics();
+    assert_eq!(metrics.commits, 1);
+    assert_eq!(metrics.reverts, 1);
+    assert_eq!(metrics.validity_cache_hits, 1);
+    assert_eq!(metrics.validity_cache_misses, 1);
+    assert_eq!(metrics.validity_cache_hit_rate(), 0.5);
+    assert!(metrics.node_cache_hits > 0);
+    assert_eq!(metrics.io, accounts.io_stats());
+}
+
+#[test]
+fn it_summarizes_the_staking_contract() {
+    let mut rng = test_rng(true);
+    let env = VolatileDatabase::new(20).unwrap();
+
+    // Before genesis, there is no staking contract.
+    let accounts = Accounts::new(env.clone());
+    assert_eq!(accounts.staking_summary(None), StakingSummary::default());
+
+    let mut genesis_builder = GenesisBuilder::default();
+    genesis_builder.with_network(NetworkId::UnitAlbatross);
+    for inactive_from in [None, None, Some(1)] {
+        genesis_builder.with_genesis_validator(
+            Address::from(&KeyPair::generate(&mut rng)),
+            Ed25519PublicKey::from([0u8; 32]),
+            BLSKeyPair::generate(&mut rng).public_key,
+            Address::default(),
+            inactive_from,
+            None,
+            false,
+        );
+    }
+    let genesis_info = genesis_builder.generate(env.clone()).unwrap();
+
+    let mut txn = env.write_transaction();
+    accounts.init(&mut (&mut txn).into(), genesis_info.accounts);
+    txn.commit();
+
+    assert_eq!(
+        accounts.staking_summary(None),
+        StakingSummary {
+            total_staked: Coin::from_u64_unchecked(3 * Policy::VALIDATOR_DEPOSIT),
+            active_validators: 2,
+            inactive_validators: 1,
+        }
+    );
+}
+
+#[test]
+fn it_initializes_from_a_streamed_genesis() {
+    let mut rng = test_rng(true);
+    let env = VolatileDatabase::new(20).unwrap();
+
+    let mut genesis_builder = GenesisBuilder::default();
+    genesis_builder.with_network(NetworkId::UnitAlbatross);
+    genesis_builder.with_genesis_validator(
+        Address::from(&KeyPair::generate(&mut rng)),
+        Ed25519PublicKey::from([0u8; 32]),
+        BLSKeyPair::generate(&mut rng).public_key,
+        Address::default(),
+        None,
+        None,
+        false,
+    );
+    for i in 1..=100u8 {
+        genesis_builder.with_basic_account(
+            Address::from([i; Address::SIZE]),
+            Coin::from_u64_unchecked(i as u64),
+        );
+    }
+    let genesis_info = genesis_builder.generate(env.clone()).unwrap();
+
+    let accounts = Accounts::new(env.clone());
+    let mut txn = env.write_transaction();
+    let root = accounts
+        .init_streaming(&mut (&mut txn).into(), genesis_info.accounts.clone())
+        .unwrap();
+    txn.commit();
+
+    assert_eq!(&root, genesis_info.block.state_root());
+    assert_eq!(accounts.get_root_hash_assert(None), root);
+
+    // An already initialized tree can't be initialized again.
+    let mut txn = env.write_transaction();
+    assert_eq!(
+        accounts.init_streaming(&mut (&mut txn).into(), genesis_info.accounts),
+        Err(AccountError::ChunkError(MerkleRadixTrieError::TrieNotEmpty))
+    );
+    txn.abort();
+    assert_eq!(accounts.get_root_hash_assert(None), root);
+}
+
+#[test]
+fn it_finds_dormant_contracts() {
+    let vesting_address = Address::from([1u8; Address::SIZE]);
+    let htlc_address = Address::from([2u8; Address::SIZE]);
+
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (
+            vesting_address.clone(),
+            Account::Vesting(VestingContract {
+                balance: Coin::from_u64_unchecked(1000),
+                owner: Address::from([9u8; Address::SIZE]),
+                start_time: 1000,
+                time_step: 100,
+                step_amount: Coin::from_u64_unchecked(300),
+                total_amount: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+        (
+            htlc_address.clone(),
+            Account::HTLC(HashedTimeLockedContract {
+                balance: Coin::from_u64_unchecked(1000),
+                sender: Address::from([8u8; Address::SIZE]),
+                recipient: Address::from([9u8; Address::SIZE]),
+                hash_root: AnyHash::default(),
+                hash_count: 1,
+                timeout: 1200,
+                total_amount: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+        (
+            Address::from([3u8; Address::SIZE]),
+            Account::Basic(BasicAccount {
+                balance: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+    ]);
+
+    assert_eq!(accounts.find_dormant_contracts(1200, None), vec![]);
+    assert_eq!(
+        accounts.find_dormant_contracts(1201, None),
+        vec![(htlc_address.clone(), DormancyReason::HtlcExpired)]
+    );
+    assert_eq!(
+        accounts.find_dormant_contracts(1400, None),
+        vec![
+            (vesting_address, DormancyReason::FullyVested),
+            (htlc_address, DormancyReason::HtlcExpired),
+        ]
+    );
+}
+
+#[test]
+fn it_rejects_blocks_with_too_many_inherents() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+    let root = accounts.get_root_hash_assert(None);
+
+    let rewards: Vec<_> = (1..=3u8)
+        .map(|i| Inherent::Reward {
+            validator_address: Address::burn_address(),
+            target: Address::from([i; Address::SIZE]),
+            value: Coin::from_u64_unchecked(1000),
+        })
+        .collect();
+    let block_state = BlockState::new(1, 1);
+
+    let mut raw_txn = env.write_transaction();
+    let mut txn = (&mut raw_txn).into();
+
+    let result = accounts.commit_with_options(
+        &mut txn,
+        &[],
+        &rewards,
+        &block_state,
+        &mut BlockLogger::empty(),
+        &CommitOptions {
+            max_inherents: 2,
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        AccountError::TooManyInherents { count: 3 }
+    );
+    assert_eq!(accounts.get_root_hash_assert(Some(&txn)), root);
+
+    let receipts = accounts
+        .commit_with_options(
+            &mut txn,
+            &[],
+            &rewards,
+            &block_state,
+            &mut BlockLogger::empty(),
+            &CommitOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(receipts.inherents.len(), 3);
+}
+
+#[test]
+fn it_filters_staking_transactions() {
+    let new_transaction = |sender: &Address, recipient: &Address| {
+        Transaction::new_basic(
+            sender.clone(),
+            recipient.clone(),
+            Coin::from_u64_unchecked(10),
+            Coin::ZERO,
+            1,
+            NetworkId::UnitAlbatross,
+        )
+    };
+    let basic_address = Address::from([1u8; Address::SIZE]);
+    let transactions = vec![
+        new_transaction(&basic_address, &basic_address),
+        new_transaction(&basic_address, &Policy::STAKING_CONTRACT_ADDRESS),
+        new_transaction(&basic_address, &basic_address),
+        new_transaction(&Policy::STAKING_CONTRACT_ADDRESS, &basic_address),
+    ];
+
+    assert_eq!(
+        Accounts::staking_transactions(&transactions),
+        vec![(1, &transactions[1]), (3, &transactions[3])]
+    );
+    assert!(Accounts::staking_transactions(&transactions[..1]).is_empty());
+}
+
+#[test]
+fn it_collects_inherent_targets() {
+    let reward = |i: u8| Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: Address::from([i; Address::SIZE]),
+        value: Coin::from_u64_unchecked(1000),
+    };
+    let inherents = vec![
+        reward(1),
+        reward(2),
+        reward(1),
+        Inherent::FinalizeBatch,
+        Inherent::FinalizeEpoch,
+    ];
+
+    assert_eq!(
+        Accounts::inherent_targets(&inherents),
+        HashSet::from([
+            Address::from([1u8; Address::SIZE]),
+            Address::from([2u8; Address::SIZE]),
+            Policy::STAKING_CONTRACT_ADDRESS,
+        ])
+    );
+    assert!(Accounts::inherent_targets(&[]).is_empty());
+}
+
+#[test]
+fn it_reconstructs_the_history_of_an_account() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+    let address = |i: u8| Address::from([i; Address::SIZE]);
+    let new_transaction = |from: u8, to: u8, value| {
+        Transaction::new_basic(
+            address(from),
+            address(to),
+            Coin::from_u64_unchecked(value),
+            Coin::ZERO,
+            1,
+            NetworkId::UnitAlbatross,
+        )
+    };
+    let commit = |transactions: &[Transaction], inherents: &[Inherent], number: u32| {
+        let mut raw_txn = env.write_transaction();
+        let receipts = accounts
+            .commit(
+                &mut (&mut raw_txn).into(),
+                transactions,
+                inherents,
+                &BlockState::new(number, number as u64),
+                &mut BlockLogger::empty(),
+            )
+            .unwrap();
+        raw_txn.commit();
+        receipts
+    };
+
+    let inherents_1 = vec![Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: address(1),
+        value: Coin::from_u64_unchecked(1000),
+    }];
+    let receipts_1 = commit(&[], &inherents_1, 1);
+
+    // The second transaction fails, so only the sender is affected.
+    let transactions_2 = vec![new_transaction(1, 2, 500), new_transaction(1, 3, 5000)];
+    let receipts_2 = commit(&transactions_2, &[], 2);
+    assert!(matches!(
+        receipts_2.transactions[1],
+        TransactionOperationReceipt::Err(..)
+    ));
+
+    let transactions_3 = vec![new_transaction(2, 1, 100)];
+    let receipts_3 = commit(&transactions_3, &[], 3);
+
+    let blocks = [
+        (1, &[][..], &inherents_1[..], &receipts_1),
+        (2, &transactions_2[..], &[][..], &receipts_2),
+        (3, &transactions_3[..], &[][..], &receipts_3),
+    ];
+
+    assert_eq!(
+        Accounts::account_history(&address(1), &blocks),
+        vec![
+            (
+                1,
+                AccountChange::Inherent {
+                    index: 0,
+                    receipt: receipts_1.inherents[0].clone(),
+                }
+            ),
+            (
+                2,
+                AccountChange::SentTransaction {
+                    index: 0,
+                    receipt: receipts_2.transactions[0].clone(),
+                }
+            ),
+            (
+                2,
+                AccountChange::SentTransaction {
+                    index: 1,
+                    receipt: receipts_2.transactions[1].clone(),
+                }
+            ),
+            (
+                3,
+                AccountChange::ReceivedTransaction {
+                    index: 0,
+                    receipt: receipts_3.transactions[0].clone(),
+                }
+            ),
+        ]
+    );
+    assert_eq!(
+        Accounts::account_history(&address(2), &blocks),
+        vec![
+            (
+                2,
+                AccountChange::ReceivedTransaction {
+                    index: 0,
+                    receipt: receipts_2.transactions[0].clone(),
+                }
+            ),
+            (
+                3,
+                AccountChange::SentTransaction {
+                    index: 0,
+                    receipt: receipts_3.transactions[0].clone(),
+                }
+            ),
+        ]
+    );
+    assert!(Accounts::account_history(&address(3), &blocks).is_empty());
+}
+
+#[test]
+fn it_verifies_signatures_if_requested() {
+    let key_pair = KeyPair::generate(&mut test_rng(true));
+    let sender = Address::from(&key_pair.public);
+
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        sender.clone(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1000),
+        }),
+    )]);
+
+    let new_transaction = |value| {
+        let mut tx = Transaction::new_basic(
+            sender.clone(),
+            Address::from([1u8; Address::SIZE]),
+            Coin::from_u64_unchecked(value),
+            Coin::from_u64_unchecked(1),
+            1,
+            NetworkId::Dummy,
+        );
+        let signature = key_pair.sign(&tx.serialize_content()[..]);
+        tx.proof = SignatureProof::from_ed25519(key_pair.public, signature).serialize_to_vec();
+        tx
+    };
+    let valid_tx = new_transaction(10);
+    let mut invalid_tx = new_transaction(20);
+    // Invalidate the signature by changing the signed content.
+    invalid_tx.value = Coin::from_u64_unchecked(30);
+
+    let options = CommitOptions {
+        verify_signatures: true,
+        ..Default::default()
+    };
+    let block_state = BlockState::new(1, 1);
+
+    let mut raw_txn = accounts.env.write_transaction();
+    let mut txn = (&mut raw_txn).into();
+
+    let result = accounts.commit_with_options(
+        &mut txn,
+        &[valid_tx.clone(), invalid_tx.clone()],
+        &[],
+        &block_state,
+        &mut BlockLogger::empty(),
+        &options,
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        AccountError::InvalidTransactionSignature { index: 1 }
+    );
+
+    // Other verification errors are not reported as signature errors.
+    let result = accounts.commit_with_options(
+        &mut txn,
+        &[valid_tx.clone(), new_transaction(0)],
+        &[],
+        &block_state,
+        &mut BlockLogger::empty(),
+        &options,
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        AccountError::InvalidTransaction(TransactionError::ZeroValue)
+    );
+
+    // Without verification, the invalid signature goes unnoticed.
+    let receipts = accounts
+        .commit_with_options(
+            &mut txn,
+            &[valid_tx, invalid_tx],
+            &[],
+            &block_state,
+            &mut BlockLogger::empty(),
+            &CommitOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(receipts.transactions.len(), 2);
+}
+
+#[test]
+fn it_computes_the_validators_of_the_staking_contract() {
+    let mut rng = test_rng(true);
+    let env = VolatileDatabase::new(20).unwrap();
+
+    // Before genesis, there is no staking contract.
+    let accounts = Accounts::new(env.clone());
+    assert_eq!(accounts.compute_validators(&VrfSeed::default(), None), None);
+
+    let mut genesis_builder = GenesisBuilder::default();
+    genesis_builder.with_network(NetworkId::UnitAlbatross);
+    for _ in 0..4 {
+        genesis_builder.with_genesis_validator(
+            Address::from(&KeyPair::generate(&mut rng)),
+            Ed25519PublicKey::from([0u8; 32]),
+            BLSKeyPair::generate(&mut rng).public_key,
+            Address::default(),
+            None,
+            None,
+            false,
+        );
+    }
+    let genesis_info = genesis_builder.generate(env.clone()).unwrap();
+
+    let mut txn = env.write_transaction();
+    accounts.init(&mut (&mut txn).into(), genesis_info.accounts);
+    txn.commit();
+
+    // The genesis builder selects the validators using the default seed.
+    assert_eq!(
+        accounts.compute_validators(&VrfSeed::default(), None),
+        genesis_info.block.validators()
+    );
+}
+
+#[test]
+fn it_rejects_misordered_inherents() {
+    let env = VolatileDatabase::new(20).unwrap();
+    let accounts = Accounts::new(env.clone());
+
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: Address::from([1u8; Address::SIZE]),
+        value: Coin::from_u64_unchecked(1000),
+    };
+    let penalize = Inherent::Penalize {
+        slot: PenalizedSlot {
+            slot: 0,
+            validator_address: Address::from([2u8; Address::SIZE]),
+            offense_event_block: 0,
+        },
+    };
+    let inherents = [reward.clone(), reward, penalize];
+    let root = accounts.get_root_hash_assert(None);
+
+    // Punishments must come before rewards.
+    let mut raw_txn = env.write_transaction();
+    let result = accounts.commit_checked(
+        &mut (&mut raw_txn).into(),
+        &[],
+        &inherents,
+        &BlockState::new(1, 1),
+        &mut BlockLogger::empty(),
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        (
+            CommitStep::Inherent(2),
+            AccountError::InvalidInherentOrder { index: 2 }
+        )
+    );
+    assert_eq!(accounts.get_root_hash_assert(Some(&raw_txn)), root);
+    raw_txn.abort();
+
+    assert_eq!(
+        Accounts::pre_validate(NetworkId::UnitAlbatross, &[], &inherents),
+        Err(AccountError::InvalidInherentOrder { index: 2 })
+    );
+
+    // The same check applies when reverting.
+    let mut raw_txn = env.write_transaction();
+    let result = accounts.revert(
+        &mut (&mut raw_txn).into(),
+        &[],
+        &inherents,
+        &BlockState::new(1, 1),
+        Receipts::default().into(),
+        &mut BlockLogger::empty(),
+    );
+    raw_txn.abort();
+    assert_eq!(
+        result.unwrap_err(),
+        AccountError::InvalidInherentOrder { index: 2 }
+    );
+}
+
+#[test]
+fn it_pre_validates_block_bodies() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let address_recipient = Address::from([2u8; Address::SIZE]);
+
+    let tx = Transaction::new_basic(
+        address_sender.clone(),
+        address_recipient.clone(),
+        Coin::from_u64_unchecked(100),
+        Coin::ZERO,
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    let reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: address_recipient.clone(),
+        value: Coin::from_u64_unchecked(100),
+    };
+
+    assert_eq!(
+        Accounts::pre_validate(NetworkId::UnitAlbatross, &[tx.clone()], &[reward.clone()]),
+        Ok(())
+    );
+
+    // Duplicate transactions.
+    assert_eq!(
+        Accounts::pre_validate(NetworkId::UnitAlbatross, &[tx.clone(), tx.clone()], &[]),
+        Err(AccountError::DuplicateTransaction { index: 1 })
+    );
+
+    // Wrong network.
+    assert_eq!(
+        Accounts::pre_validate(NetworkId::Main, &[tx.clone()], &[]),
+        Err(AccountError::InvalidTransaction(
+            TransactionError::ForeignNetwork
+        ))
+    );
+
+    // Inconsistent fields.
+    let mut self_transfer = tx.clone();
+    self_transfer.recipient = address_sender;
+    assert_eq!(
+        Accounts::pre_validate(NetworkId::UnitAlbatross, &[self_transfer], &[]),
+        Err(AccountError::InvalidTransaction(
+            TransactionError::SenderEqualsRecipient
+        ))
+    );
+
+    let mut wrong_type = tx.clone();
+    wrong_type.recipient = Policy::STAKING_CONTRACT_ADDRESS;
+    assert_eq!(
+        Accounts::pre_validate(NetworkId::UnitAlbatross, &[wrong_type], &[]),
+        Err(AccountError::InvalidTransaction(
+            TransactionError::InvalidForRecipient
+        ))
+    );
+
+    let mut zero_value = tx;
+    zero_value.value = Coin::ZERO;
+    assert_eq!(
+        Accounts::pre_validate(NetworkId::UnitAlbatross, &[zero_value], &[]),
+        Err(AccountError::InvalidTransaction(
+            TransactionError::ZeroValue
+        ))
+    );
+
+    // Rewards to the staking contract.
+    let staking_reward = Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: Policy::STAKING_CONTRACT_ADDRESS,
+        value: Coin::from_u64_unchecked(100),
+    };
+    assert_eq!(
+        Accounts::pre_validate(NetworkId::UnitAlbatross, &[], &[reward, staking_reward]),
+        Err(AccountError::InvalidInherent)
+    );
+}
+
+#[test]
+fn it_explains_transactions() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let address_recipient = Address::from([2u8; Address::SIZE]);
+    let sender = Account::Basic(BasicAccount {
+        balance: Coin::from_u64_unchecked(1000),
+    });
+
+    let accounts =
+        TestCommitRevert::with_initial_state(&[(address_sender.clone(), sender.clone())]);
+    let root = accounts.get_root_hash_assert(None);
+
+    let tx = |value: u64, fee: u64| {
+        Transaction::new_basic(
+            address_sender.clone(),
+            address_recipient.clone(),
+            Coin::from_u64_unchecked(value),
+            Coin::from_u64_unchecked(fee),
+            1,
+            NetworkId::Main,
+        )
+    };
+    let block_state = BlockState::new(1, 1);
+
+    // A successful transfer moves the value and the fee.
+    let explanation = accounts
+        .explain_transaction(&tx(300, 10), &block_state)
+        .unwrap();
+    assert_eq!(explanation.sender_before, sender);
+    assert_eq!(
+        explanation.sender_after,
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(690),
+        })
+    );
+    assert_eq!(explanation.recipient_before, Account::default());
+    assert_eq!(
+        explanation.recipient_after,
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(300),
+        })
+    );
+    assert_eq!(explanation.value, Coin::from_u64_unchecked(300));
+    assert_eq!(explanation.fee, Coin::from_u64_unchecked(10));
+    assert!(matches!(explanation.receipt, OperationReceipt::Ok(_)));
+
+    // A failing transfer only deducts the fee.
+    let explanation = accounts
+        .explain_transaction(&tx(2000, 10), &block_state)
+        .unwrap();
+    assert_eq!(
+        explanation.sender_after,
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(990),
+        })
+    );
+    assert_eq!(explanation.recipient_after, Account::default());
+    assert!(matches!(
+        explanation.receipt,
+        OperationReceipt::Err(_, FailReason::InsufficientFunds)
+    ));
+
+    // A transaction whose fee can't be paid can't be included at all.
+    assert_eq!(
+        accounts
+            .explain_transaction(&tx(10, 2000), &block_state)
+            .map(|_| ()),
+        Err(AccountError::InsufficientFunds {
+            needed: Coin::from_u64_unchecked(2000),
+            balance: Coin::from_u64_unchecked(1000),
+        })
+    );
+
+    // The state is left untouched.
+    assert_eq!(accounts.get_root_hash_assert(None), root);
+    assert_eq!(accounts.get_complete(&address_sender, None), sender);
+}
+
+#[test]
+fn it_checks_for_a_minimum_balance() {
+    let address = Address::from([1u8; Address::SIZE]);
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        address.clone(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1000),
+        }),
+    )]);
+
+    assert!(accounts.has_balance_at_least(&address, Coin::from_u64_unchecked(1000), None));
+    assert!(!accounts.has_balance_at_least(&address, Coin::from_u64_unchecked(1001), None));
+
+    // Missing accounts have a balance of zero.
+    let address_missing = Address::from([2u8; Address::SIZE]);
+    assert!(accounts.has_balance_at_least(&address_missing, Coin::ZERO, None));
+    assert!(!accounts.has_balance_at_least(&address_missing, Coin::from_u64_unchecked(1), None));
+}
+
+#[test]
+fn it_streams_the_receipts_of_a_commit() {
+    let address_sender = Address::from([1u8; Address::SIZE]);
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        address_sender.clone(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1000),
+        }),
+    )]);
+
+    let tx = |value: u64| {
+        Transaction::new_basic(
+            address_sender.clone(),
+            Address::from([2u8; Address::SIZE]),
+            Coin::from_u64_unchecked(value),
+            Coin::from_u64_unchecked(1),
+            1,
+            NetworkId::Main,
+        )
+    };
+    let transactions = [tx(100), tx(2000)];
+    let inherents = [Inherent::Reward {
+        validator_address: Address::burn_address(),
+        target: Address::from([3u8; Address::SIZE]),
+        value: Coin::from_u64_unchecked(10000),
+    }];
+    let block_state = BlockState::new(1, 1);
+
+    let mut raw_txn = accounts.env.write_transaction();
+    let receipts = accounts
+        .commit(
+            &mut (&mut raw_txn).into(),
+            &transactions,
+            &inherents,
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .unwrap();
+    let root = accounts.get_root_hash_assert(Some(&raw_txn));
+    raw_txn.abort();
+
+    let mut expected = vec![Receipts::VERSION];
+    for receipt in &receipts.transactions {
+        receipt.serialize_to_writer(&mut expected).unwrap();
+    }
+    for receipt in &receipts.inherents {
+        receipt.serialize_to_writer(&mut expected).unwrap();
+    }
+
+    // The streamed receipts are the same as the ones returned by `commit`, preceded by the
+    // version byte.
+    let mut raw_txn = accounts.env.write_transaction();
+    let mut streamed = vec![];
+    accounts
+        .commit_streaming_receipts(
+            &mut (&mut raw_txn).into(),
+            &transactions,
+            &inherents,
+            &block_state,
+            &mut BlockLogger::empty(),
+            &mut streamed,
+        )
+        .unwrap();
+    assert_eq!(streamed, expected);
+    assert_eq!(accounts.get_root_hash_assert(Some(&raw_txn)), root);
+    raw_txn.abort();
+
+    // If the sink fails in the middle of the block, the commit is aborted with an error.
+    let mut buffer = vec![0u8; 1 + receipts.transactions[0].serialize_to_vec().len()];
+    let mut sink: &mut [u8] = &mut buffer;
+    let mut raw_txn = accounts.env.write_transaction();
+    let result = accounts.commit_streaming_receipts(
+        &mut (&mut raw_txn).into(),
+        &transactions,
+        &inherents,
+        &block_state,
+        &mut BlockLogger::empty(),
+        &mut sink,
+    );
+    assert!(matches!(result, Err(AccountError::ReceiptSink(_))));
+    raw_txn.abort();
+    assert_ne!(accounts.get_root_hash_assert(None), root);
+}
+
+#[cfg(feature = "zkp")]
+#[test]
+fn it_computes_the_pedersen_state_commitment() {
+    let address = Address::from([1u8; Address::SIZE]);
+    let state = |balance| {
+        TestCommitRevert::with_initial_state(&[(
+            address.clone(),
+            Account::Basic(BasicAccount {
+                balance: Coin::from_u64_unchecked(balance),
+            }),
+        )])
+    };
+
+    let accounts = state(1000);
+    let commitment = accounts.pedersen_state_commitment(None);
+
+    assert_eq!(
+        commitment,
+        nimiq_zkp_primitives::state_commitment(&accounts.get_root_hash_assert(None))
+    );
+    assert_eq!(commitment, state(1000).pedersen_state_commitment(None));
+    assert_ne!(commitment, state(999).pedersen_state_commitment(None));
+}
+
+#[cfg(feature = "migration")]
+#[test]
+fn it_commits_with_type_overrides() {
+    use std::collections::HashMap;
+
+    let address = Address::from([1u8; Address::SIZE]);
+    let accounts = TestCommitRevert::with_initial_state(&[(
+        address.clone(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1000),
+        }),
+    )]);
+
+    // The transaction already treats the account as the vesting contract it is migrated to.
+    let mut tx = Transaction::new_basic(
+        address.clone(),
+        Address::from([2u8; Address::SIZE]),
+        Coin::from_u64_unchecked(100),
+        Coin::from_u64_unchecked(1),
+        1,
+        NetworkId::Dummy,
+    );
+    tx.sender_type = AccountType::Vesting;
+    let block_state = BlockState::new(1, 1);
+
+    let mut raw_txn = accounts.env.write_transaction();
+    let mut txn = (&mut raw_txn).into();
+
+    let result = accounts.commit(
+        &mut txn,
+        &[tx.clone()],
+        &[],
+        &block_state,
+        &mut BlockLogger::empty(),
+    );
+    assert_eq!(
+        result.unwrap_err(),
+        AccountError::TypeMismatch {
+            expected: AccountType::Vesting,
+            got: AccountType::Basic,
+        }
+    );
+
+    let overrides = HashMap::from([(address.clone(), AccountType::Basic)]);
+    let receipts = accounts
+        .commit_with_type_overrides(
+            &mut txn,
+            &[tx],
+            &[],
+            &block_state,
+            &mut BlockLogger::empty(),
+            &overrides,
+        )
+        .unwrap();
+    assert!(matches!(
+        receipts.transactions[0],
+        TransactionOperationReceipt::Ok(_)
+    ));
+    assert_eq!(
+        accounts.get_complete(&address, Some(&txn)).balance(),
+        Coin::from_u64_unchecked(899)
+    );
+}
+
+#[cfg(feature = "bulk-credit")]
+#[test]
+fn it_credits_accounts_in_bulk() {
+    let address_existing = Address::from([1u8; Address::SIZE]);
+    let address_new = Address::from([2u8; Address::SIZE]);
+    let address_vesting = Address::from([3u8; Address::SIZE]);
+    let vesting = Account::Vesting(VestingContract {
+        balance: Coin::from_u64_unchecked(1000),
+        owner: address_existing.clone(),
+        start_time: 0,
+        time_step: 100,
+        step_amount: Coin::from_u64_unchecked(100),
+        total_amount: Coin::from_u64_unchecked(1000),
+    });
+
+    let accounts = TestCommitRevert::with_initial_state(&[
+        (
+            address_existing.clone(),
+            Account::Basic(BasicAccount {
+                balance: Coin::from_u64_unchecked(1000),
+            }),
+        ),
+        (address_vesting.clone(), vesting.clone()),
+    ]);
+
+    let mut raw_txn = accounts.env.write_transaction();
+    let mut txn = (&mut raw_txn).into();
+
+    // Existing basic accounts are topped up and missing ones are created.
+    let root = accounts
+        .bulk_credit(
+            &mut txn,
+            &[
+                (address_existing.clone(), Coin::from_u64_unchecked(500)),
+                (address_new.clone(), Coin::from_u64_unchecked(200)),
+            ],
+        )
+        .unwrap();
+    assert_eq!(root, accounts.get_root_hash_assert(Some(&txn)));
+    assert_eq!(
+        accounts.get_complete(&address_existing, Some(&txn)),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(1500),
+        })
+    );
+    assert_eq!(
+        accounts.get_complete(&address_new, Some(&txn)),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(200),
+        })
+    );
+
+    // Other account types can't be credited.
+    assert_eq!(
+        accounts.bulk_credit(
+            &mut txn,
+            &[(address_vesting.clone(), Coin::from_u64_unchecked(500))]
+        ),
+        Err(AccountError::TypeMismatch {
+            expected: AccountType::Basic,
+            got: AccountType::Vesting,
+        })
+    );
+    assert_eq!(accounts.get_complete(&address_vesting, Some(&txn)), vesting);
+}
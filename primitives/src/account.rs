@@ -1,16 +1,18 @@
 use std::convert::TryFrom;
 
+use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
 use strum_macros::Display;
 use thiserror::Error;
 
 use crate::{
     coin::{Coin, CoinConvertError, CoinParseError, CoinUnderflowError},
+    networks::NetworkId,
     transaction::TransactionError,
     trie::error::MerkleRadixTrieError,
 };
 
-#[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Display)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash, Debug, Display)]
 #[repr(u8)]
 #[cfg_attr(
     any(feature = "serde-derive", feature = "ts-types"),
@@ -97,6 +99,36 @@ pub enum AccountError {
     AlreadyExistentAddress { address: Address },
     #[error("Error during chunk processing: {0}")]
     ChunkError(#[from] MerkleRadixTrieError),
+    #[error("Failed to write receipt: {0}")]
+    ReceiptSink(String),
+    #[error("Transaction {index} is for network {got}, but expected {expected}")]
+    WrongNetwork {
+        expected: NetworkId,
+        got: NetworkId,
+        index: u16,
+    },
+    #[error("Account would exceed the maximum trie depth of {max_depth}")]
+    TrieTooDeep { max_depth: usize },
+    #[error("Accounts tree root mismatch: expected {expected}, but got {got}")]
+    RootMismatch {
+        expected: Blake2bHash,
+        got: Blake2bHash,
+    },
+    #[error("Too many inherents: {count}")]
+    TooManyInherents { count: usize },
+    #[error("Transaction {index} is a duplicate")]
+    DuplicateTransaction { index: u16 },
+    #[error("Transaction {index} has an invalid signature")]
+    InvalidTransactionSignature { index: u16 },
+    #[error("Account {address} violates an invariant: {invariant}")]
+    InvariantViolation {
+        address: Address,
+        invariant: &'static str,
+    },
+    #[error("Unsupported receipt version {version}, supported up to {supported}")]
+    UnsupportedReceiptVersion { version: u8, supported: u8 },
+    #[error("Inherent {index} is out of order")]
+    InvalidInherentOrder { index: u16 },
 }
 
 impl From<CoinUnderflowError> for AccountError {
@@ -149,6 +181,26 @@ pub enum FailReason {
     ChunkError,
     #[error("Failing transaction failed for unknown reason")]
     Incomplete,
+    #[error("Failed to write receipt")]
+    ReceiptSink,
+    #[error("Wrong network")]
+    WrongNetwork,
+    #[error("Maximum trie depth exceeded")]
+    TrieTooDeep,
+    #[error("Accounts tree root mismatch")]
+    RootMismatch,
+    #[error("Too many inherents")]
+    TooManyInherents,
+    #[error("Duplicate transaction")]
+    DuplicateTransaction,
+    #[error("Invalid transaction signature")]
+    InvalidTransactionSignature,
+    #[error("Account invariant violated")]
+    InvariantViolation,
+    #[error("Unsupported receipt version")]
+    UnsupportedReceiptVersion,
+    #[error("Invalid inherent order")]
+    InvalidInherentOrder,
 }
 
 impl From<AccountError> for FailReason {
@@ -170,6 +222,18 @@ impl From<AccountError> for FailReason {
             AccountError::NonExistentAddress { .. } => FailReason::NonExistentAddress,
             AccountError::AlreadyExistentAddress { .. } => FailReason::AlreadyExistentAddress,
             AccountError::ChunkError(_) => FailReason::ChunkError,
+            AccountError::ReceiptSink(_) => FailReason::ReceiptSink,
+            AccountError::WrongNetwork { .. } => FailReason::WrongNetwork,
+            AccountError::TrieTooDeep { .. } => FailReason::TrieTooDeep,
+            AccountError::RootMismatch { .. } => FailReason::RootMismatch,
+            AccountError::TooManyInherents { .. } => FailReason::TooManyInherents,
+            AccountError::DuplicateTransaction { .. } => FailReason::DuplicateTransaction,
+            AccountError::InvalidTransactionSignature { .. } => {
+                FailReason::InvalidTransactionSignature
+            }
+            AccountError::InvariantViolation { .. } => FailReason::InvariantViolation,
+            AccountError::UnsupportedReceiptVersion { .. } => FailReason::UnsupportedReceiptVersion,
+            AccountError::InvalidInherentOrder { .. } => FailReason::InvalidInherentOrder,
         }
     }
 }
@@ -107,6 +107,10 @@ impl Policy {
     /// deletion fails and thus a fee gets deducted from it.
     pub const VALIDATOR_DEPOSIT: u64 = 10_000_000_000;
 
+    /// The minimum fee per byte of a serialized transaction in Lunas (1 NIM = 100,000 Lunas) that
+    /// is required by the consensus rules. Nodes may require higher fees in their mempool rules.
+    pub const MINIMUM_FEE_PER_BYTE: u64 = 0;
+
     /// The stake necessary to create a staker in Lunas (1 NIM = 100,000 Lunas).
     /// This minimum is applied to the amount of:
     ///     - non-retired stake
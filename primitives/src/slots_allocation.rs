@@ -22,7 +22,7 @@ use nimiq_bls::{lazy::LazyPublicKey as LazyBlsPublicKey, G2Projective, PublicKey
 use nimiq_hash::{Hash, HashOutput};
 use nimiq_keys::{Address, Ed25519PublicKey as SchnorrPublicKey};
 
-use crate::{merkle_tree::merkle_tree_construct, policy::Policy};
+use crate::{coin::Coin, merkle_tree::merkle_tree_construct, policy::Policy};
 
 /// This is the depth of the PKTree circuit.
 pub const PK_TREE_DEPTH: usize = 5;
@@ -39,6 +39,20 @@ pub struct Slot {
     pub validator: Validator,
 }
 
+/// The owner of a proposer slot together with its economic weight, as returned by
+/// `AbstractBlockchain::get_slot_owner_details_at`.
+pub struct SlotOwnerDetails {
+    /// The proposer slot, including the validator owning it.
+    pub slot: Slot,
+    /// The number of slots owned by the validator in the slot's epoch.
+    pub num_slots: u16,
+    /// The total stake of the validator according to the current staking contract, which might
+    /// differ from its stake at the slot's block number. This is `None` if the validator doesn't
+    /// exist anymore or the staking contract isn't available, e.g. for light nodes or if the
+    /// accounts tree is incomplete.
+    pub current_total_stake: Option<Coin>,
+}
+
 /// A validator that owns some slots.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde-derive", derive(serde::Serialize, serde::Deserialize))]
@@ -201,6 +215,47 @@ impl Validators {
     pub fn iter(&self) -> Iter<Validator> {
         self.validators.iter()
     }
+
+    /// Computes the difference between this validator set and the given `next` one.
+    /// Validators are matched by their address.
+    pub fn diff(&self, next: &Validators) -> ValidatorSetDiff {
+        let mut diff = ValidatorSetDiff::default();
+
+        for validator in next.iter() {
+            match self.get_validator_by_address(&validator.address) {
+                None => diff.added.push(validator.clone()),
+                Some(previous) => {
+                    if previous.signing_key != validator.signing_key
+                        || previous.voting_key != validator.voting_key
+                    {
+                        diff.rotated.push(validator.address.clone());
+                    }
+                    diff.retained.push(validator.clone());
+                }
+            }
+        }
+
+        for validator in self.iter() {
+            if !next.validator_map.contains_key(&validator.address) {
+                diff.removed.push(validator.clone());
+            }
+        }
+
+        diff
+    }
+}
+
+/// The difference between two validator sets, usually of two consecutive epochs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidatorSetDiff {
+    /// Validators that are only part of the new set.
+    pub added: Vec<Validator>,
+    /// Validators that are only part of the old set.
+    pub removed: Vec<Validator>,
+    /// Validators that are part of both sets. Contains the validators as they are in the new set.
+    pub retained: Vec<Validator>,
+    /// Addresses of the retained validators whose voting or signing key changed.
+    pub rotated: Vec<Address>,
 }
 
 impl Hash for Validators {
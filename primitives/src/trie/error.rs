@@ -25,8 +25,12 @@ pub enum MerkleRadixTrieError {
     InvalidChunk(&'static str),
     #[error("Trie is not complete")]
     IncompleteTrie,
+    #[error("Trie is not empty")]
+    TrieNotEmpty,
     #[error("Serialization error")]
     Serialization(#[from] nimiq_serde::DeserializeError),
+    #[error("Value would exceed the maximum trie depth of {0}.")]
+    TrieTooDeep(usize),
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -42,6 +42,18 @@ pub enum Inherent {
 }
 
 impl Inherent {
+    /// Returns the class that determines the position of this inherent within a block. The
+    /// inherents of a block are ordered by non-decreasing class: punishments come first, followed
+    /// by rewards and finally the batch and epoch finalization.
+    pub fn ordering_class(&self) -> u8 {
+        match self {
+            Inherent::Penalize { .. } | Inherent::Jail { .. } => 0,
+            Inherent::Reward { .. } => 1,
+            Inherent::FinalizeBatch => 2,
+            Inherent::FinalizeEpoch => 3,
+        }
+    }
+
     pub fn target(&self) -> &Address {
         match self {
             Inherent::Reward { target, .. } => target,
@@ -334,6 +334,21 @@ impl Transaction {
             return Ok(());
         }
 
+        self.verify_fields(network_id)?;
+
+        // Check transaction validity for sender account.
+        AccountType::verify_outgoing_transaction(self)?;
+
+        // Check transaction validity for recipient account.
+        AccountType::verify_incoming_transaction(self)?;
+
+        Ok(())
+    }
+
+    /// Performs the checks of `verify` that only depend on the fields of the transaction, i.e.
+    /// without the account type specific checks of the data and proof. In particular, no
+    /// signatures are verified.
+    pub fn verify_fields(&self, network_id: NetworkId) -> Result<(), TransactionError> {
         if self.recipient == Policy::STAKING_CONTRACT_ADDRESS
             && self.recipient_type != AccountType::Staking
         {
@@ -379,12 +394,6 @@ impl Transaction {
             None => return Err(TransactionError::Overflow),
         }
 
-        // Check transaction validity for sender account.
-        AccountType::verify_outgoing_transaction(self)?;
-
-        // Check transaction validity for recipient account.
-        AccountType::verify_incoming_transaction(self)?;
-
         Ok(())
     }
 
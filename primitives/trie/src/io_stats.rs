@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The number of node operations performed by a `MerkleRadixTrie`, as returned by
+/// `MerkleRadixTrie::io_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IoStats {
+    /// The number of nodes read from the database. Reads served by the node cache are not
+    /// included.
+    pub node_reads: u64,
+    /// The number of nodes written to the database.
+    pub node_writes: u64,
+    /// The number of nodes removed from the database.
+    pub node_removals: u64,
+    /// The number of times the root hash was updated.
+    pub root_updates: u64,
+}
+
+/// The counters behind `IoStats`. They are updated with relaxed atomics, so they are cheap enough
+/// to be always enabled.
+#[derive(Debug, Default)]
+pub(crate) struct IoCounters {
+    node_reads: AtomicU64,
+    node_writes: AtomicU64,
+    node_removals: AtomicU64,
+    root_updates: AtomicU64,
+}
+
+impl IoCounters {
+    pub(crate) fn count_node_read(&self) {
+        self.node_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_node_write(&self) {
+        self.node_writes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_node_removal(&self) {
+        self.node_removals.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_root_update(&self) {
+        self.root_updates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> IoStats {
+        IoStats {
+            node_reads: self.node_reads.load(Ordering::Relaxed),
+            node_writes: self.node_writes.load(Ordering::Relaxed),
+            node_removals: self.node_removals.load(Ordering::Relaxed),
+            root_updates: self.root_updates.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        self.node_reads.store(0, Ordering::Relaxed);
+        self.node_writes.store(0, Ordering::Relaxed);
+        self.node_removals.store(0, Ordering::Relaxed);
+        self.root_updates.store(0, Ordering::Relaxed);
+    }
+}
@@ -1,4 +1,8 @@
+mod io_stats;
+mod node_cache;
 mod transaction;
 pub mod trie;
 
+pub use io_stats::IoStats;
+pub use node_cache::TrieNodeCache;
 pub use transaction::WriteTransactionProxy;
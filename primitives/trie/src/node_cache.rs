@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use nimiq_primitives::{key_nibbles::KeyNibbles, trie::trie_node::TrieNode};
+use parking_lot::Mutex;
+
+/// A cache of trie nodes that is shared across read transactions.
+///
+/// The cached nodes are only valid for the database snapshot they were read from. As soon as a
+/// read transaction on a newer snapshot (i.e. one that sees the changes of a committed write
+/// transaction) consults the cache, all entries are dropped. Write transactions and read
+/// transactions on older snapshots bypass the cache entirely, so uncommitted or outdated nodes
+/// are never served.
+#[derive(Debug)]
+pub struct TrieNodeCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    snapshot_id: u64,
+    nodes: HashMap<KeyNibbles, Option<TrieNode>>,
+}
+
+impl TrieNodeCache {
+    /// Creates an empty cache holding at most `capacity` nodes.
+    pub fn new(capacity: usize) -> Self {
+        TrieNodeCache {
+            capacity,
+            state: Mutex::new(CacheState::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the node at `key` as seen by the snapshot `snapshot_id`, calling `load` to read it
+    /// from the database if it isn't cached yet.
+    pub(crate) fn get_or_load<F>(
+        &self,
+        snapshot_id: u64,
+        key: &KeyNibbles,
+        load: F,
+    ) -> Option<TrieNode>
+    where
+        F: FnOnce() -> Option<TrieNode>,
+    {
+        {
+            let mut state = self.state.lock();
+            if snapshot_id > state.snapshot_id {
+                state.snapshot_id = snapshot_id;
+                state.nodes.clear();
+            } else if snapshot_id < state.snapshot_id {
+                return load();
+            }
+
+            if let Some(node) = state.nodes.get(key) {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return node.clone();
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let node = load();
+
+        let mut state = self.state.lock();
+        if state.snapshot_id == snapshot_id && state.nodes.len() < self.capacity {
+            state.nodes.insert(key.clone(), node.clone());
+        }
+
+        node
+    }
+
+    /// The maximum number of nodes held by the cache.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of nodes currently held by the cache.
+    pub fn len(&self) -> usize {
+        self.state.lock().nodes.len()
+    }
+
+    /// Returns true if the cache currently holds no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of lookups that were served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of lookups that had to read from the database.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// The fraction of lookups that were served from the cache, or 0 if there were none yet.
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits();
+        let total = hits + self.misses();
+        if total == 0 {
+            return 0.0;
+        }
+        hits as f64 / total as f64
+    }
+}
@@ -25,8 +25,9 @@ use nimiq_primitives::{
 use nimiq_serde::{Deserialize, Serialize};
 
 use crate::{
+    io_stats::IoCounters,
     transaction::{OldValue, TransactionExt as _},
-    WriteTransactionProxy,
+    IoStats, TrieNodeCache, WriteTransactionProxy,
 };
 
 /// A Merkle Radix Trie is a hybrid between a Merkle tree and a Radix trie. Like a Merkle tree each
@@ -43,6 +44,9 @@ use crate::{
 #[derive(Debug)]
 pub struct MerkleRadixTrie {
     db: TableProxy,
+    node_cache: Option<TrieNodeCache>,
+    max_depth: Option<usize>,
+    io_counters: IoCounters,
 }
 
 /// Counts the number of updates performed.
@@ -96,17 +100,32 @@ impl CountUpdates {
 impl MerkleRadixTrie {
     /// Start a new Merkle Radix Trie with the given Environment and the given name.
     pub fn new(db: DatabaseProxy, name: &str) -> Self {
-        Self::new_impl(db, name, false)
+        Self::new_impl(db, name, false, None)
     }
 
     pub fn new_incomplete(env: DatabaseProxy, name: &str) -> Self {
-        Self::new_impl(env, name, true)
+        Self::new_impl(env, name, true, None)
     }
 
-    fn new_impl(db: DatabaseProxy, name: &str, incomplete: bool) -> Self {
+    /// Like `new`, but read transactions share a cache of up to `cache_capacity` trie nodes.
+    pub fn new_with_node_cache(db: DatabaseProxy, name: &str, cache_capacity: usize) -> Self {
+        Self::new_impl(db, name, false, Some(TrieNodeCache::new(cache_capacity)))
+    }
+
+    fn new_impl(
+        db: DatabaseProxy,
+        name: &str,
+        incomplete: bool,
+        node_cache: Option<TrieNodeCache>,
+    ) -> Self {
         let table = db.open_table(name.to_string());
 
-        let tree = MerkleRadixTrie { db: table };
+        let tree = MerkleRadixTrie {
+            db: table,
+            node_cache,
+            max_depth: None,
+            io_counters: IoCounters::default(),
+        };
 
         let mut txn = db.write_transaction();
         tree.init_root(&mut (&mut txn).into(), incomplete);
@@ -115,6 +134,13 @@ impl MerkleRadixTrie {
         tree
     }
 
+    /// Makes `put` reject values that would end up more than `max_depth` nodes below the root.
+    /// The depth is checked along the path of the inserted key only.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
     fn init_root(&self, txn: &mut WriteTransactionProxy, incomplete: bool) {
         if self.get_root(txn).is_none() {
             let root = if incomplete {
@@ -126,16 +152,43 @@ impl MerkleRadixTrie {
         }
     }
 
-    pub fn init(&self, txn: &mut WriteTransactionProxy, values: Vec<TrieItem>) {
-        assert!(self.is_complete(txn));
-        assert_eq!(self.num_leaves(txn), 0);
-        assert_eq!(self.num_hybrids(txn), 0);
+    pub fn init(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        values: impl IntoIterator<Item = TrieItem>,
+    ) {
+        self.try_init(txn, values)
+            .expect("Tree must be empty and complete");
+    }
+
+    /// Initializes the trie with the given values like `init`, but returns an error instead of
+    /// panicking if the trie is not empty and complete or if a value can't be stored. The trie
+    /// might have been partially initialized in that case, so the transaction should be aborted.
+    pub fn try_init(
+        &self,
+        txn: &mut WriteTransactionProxy,
+        values: impl IntoIterator<Item = TrieItem>,
+    ) -> Result<(), MerkleRadixTrieError> {
+        if !self.is_complete(txn) {
+            return Err(MerkleRadixTrieError::IncompleteTrie);
+        }
+        if self.num_leaves(txn) != 0 || self.num_hybrids(txn) != 0 {
+            return Err(MerkleRadixTrieError::TrieNotEmpty);
+        }
 
         for item in values {
+            if item.key.is_empty() {
+                return Err(MerkleRadixTrieError::RootCantHaveValue);
+            }
+            if let Some(max_depth) = self.max_depth {
+                if self.depth_after_put(txn, &item.key, &None) > max_depth {
+                    return Err(MerkleRadixTrieError::TrieTooDeep(max_depth));
+                }
+            }
             self.put_raw(txn, &item.key, item.value, &None);
         }
 
-        self.update_root(txn).expect("Tree must be complete");
+        self.update_root(txn)
     }
 
     /// Clears the database and initializes it as incomplete.
@@ -246,15 +299,40 @@ impl MerkleRadixTrie {
         (num_branches, num_hybrids, num_leaves)
     }
 
+    /// Returns the node cache shared by the read transactions, if enabled.
+    pub fn node_cache(&self) -> Option<&TrieNodeCache> {
+        self.node_cache.as_ref()
+    }
+
+    /// Returns the number of node operations performed since the trie was created or the
+    /// statistics were last reset.
+    pub fn io_stats(&self) -> IoStats {
+        self.io_counters.snapshot()
+    }
+
+    /// Resets the statistics returned by `io_stats` to zero.
+    pub fn reset_io_stats(&self) {
+        self.io_counters.reset()
+    }
+
     fn get_node(&self, txn: &TransactionProxy, key: &KeyNibbles) -> Option<TrieNode> {
-        txn.get_node(&self.db, key)
+        let load = || {
+            self.io_counters.count_node_read();
+            txn.get_node(&self.db, key)
+        };
+        match (&self.node_cache, txn.snapshot_id()) {
+            (Some(cache), Some(snapshot_id)) => cache.get_or_load(snapshot_id, key, load),
+            _ => load(),
+        }
     }
 
     fn put_node(&self, txn: &mut WriteTransactionProxy, node: &TrieNode, old_value: OldValue) {
+        self.io_counters.count_node_write();
         txn.put_node(&self.db, node, old_value)
     }
 
     fn remove_node(&self, txn: &mut WriteTransactionProxy, key: &KeyNibbles, old_value: OldValue) {
+        self.io_counters.count_node_removal();
         txn.remove_node(&self.db, key, old_value)
     }
 
@@ -274,10 +352,117 @@ impl MerkleRadixTrie {
             .map(|v| T::deserialize_from_vec(&v).unwrap()))
     }
 
+    /// Gets the values at the given keys like `get`, aligned with `keys`. Every distinct key is
+    /// only looked up once and the lookups are done in ascending key order. Returns an error if
+    /// any of the keys is not within the complete part of the trie.
+    ///
+    /// This doesn't walk the trie from the root. Nodes are stored by their key, so each lookup
+    /// reads a single node, which is fewer reads than a walk that shares the path to consecutive
+    /// keys would need.
+    pub fn get_many<T: Deserialize + Clone>(
+        &self,
+        txn: &TransactionProxy,
+        keys: &[KeyNibbles],
+    ) -> Result<Vec<Option<T>>, IncompleteTrie> {
+        let missing_range = self.get_missing_range(txn);
+        let mut values: BTreeMap<&KeyNibbles, Option<T>> =
+            keys.iter().map(|key| (key, None)).collect();
+
+        for (key, value) in values.iter_mut() {
+            if !self.is_within_complete_part(key, &missing_range) {
+                return Err(IncompleteTrie);
+            }
+            *value = self
+                .get_raw(txn, key)
+                .map(|v| T::deserialize_from_vec(&v).unwrap());
+        }
+
+        Ok(keys.iter().map(|key| values[key].clone()).collect())
+    }
+
     fn get_raw(&self, txn: &TransactionProxy, key: &KeyNibbles) -> Option<Vec<u8>> {
         self.get_node(txn, key)?.value
     }
 
+    /// Returns the value at the given key like `get`, together with the depth of its node, i.e.
+    /// the number of nodes below the root on the path to it. The depth is the same as the one
+    /// limited by `with_max_depth`.
+    pub fn get_with_depth<T: Deserialize>(
+        &self,
+        txn: &TransactionProxy,
+        key: &KeyNibbles,
+    ) -> Result<Option<(T, usize)>, IncompleteTrie> {
+        let missing_range = self.get_missing_range(txn);
+        if !self.is_within_complete_part(key, &missing_range) {
+            return Err(IncompleteTrie);
+        }
+
+        let mut cur_node = self
+            .get_root(txn)
+            .expect("Merkle Radix Trie must have a root node!");
+        let mut depth = 0;
+
+        loop {
+            if cur_node.key == *key {
+                return Ok(cur_node
+                    .value
+                    .map(|v| (T::deserialize_from_vec(&v).unwrap(), depth)));
+            }
+
+            if !cur_node.key.is_prefix_of(key) {
+                return Ok(None);
+            }
+
+            match cur_node.child_key(key, &missing_range) {
+                Err(_) => return Ok(None),
+                Ok(child_key) => {
+                    cur_node = match self.get_node(txn, &child_key) {
+                        Some(node) => node,
+                        None => return Ok(None),
+                    };
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the hash of the node at the top of the subtree that contains all keys starting with
+    /// `prefix`, or `None` if there are no such keys. The node hash also commits to the node's
+    /// key, so two tries have the same subtree hash for a prefix if and only if they contain the
+    /// same values below that prefix. The hashes are only up to date after `update_root`.
+    pub fn get_subtree_hash(
+        &self,
+        txn: &TransactionProxy,
+        prefix: &KeyNibbles,
+    ) -> Result<Option<Blake2bHash>, IncompleteTrie> {
+        if !self.is_complete(txn) {
+            return Err(IncompleteTrie);
+        }
+
+        let mut cur_node = self
+            .get_root(txn)
+            .expect("Merkle Radix Trie must have a root node!");
+
+        loop {
+            if prefix.is_prefix_of(&cur_node.key) {
+                return Ok(cur_node.hash());
+            }
+
+            if !cur_node.key.is_prefix_of(prefix) {
+                return Ok(None);
+            }
+
+            match cur_node.child_key(prefix, &None) {
+                Err(_) => return Ok(None),
+                Ok(child_key) => {
+                    cur_node = self
+                        .get_node(txn, &child_key)
+                        .expect("Child node must exist in a complete trie");
+                }
+            }
+        }
+    }
+
     /// Insert a value into the Merkle Radix Trie at the given key. If the key already exists then
     /// it will overwrite it. You can't use this function to check the existence of a given key.
     pub fn put<T: Serialize>(
@@ -289,6 +474,11 @@ impl MerkleRadixTrie {
         // PITODO: Return value needs to change, we don't need the error anymore
         let missing_range = self.get_missing_range(txn);
         if self.is_within_complete_part(key, &missing_range) {
+            if let Some(max_depth) = self.max_depth {
+                if self.depth_after_put(txn, key, &missing_range) > max_depth {
+                    return Err(MerkleRadixTrieError::TrieTooDeep(max_depth));
+                }
+            }
             self.put_raw(txn, key, value.serialize_to_vec(), &missing_range);
         } else {
             self.update_within_missing_part_raw(txn, key, &missing_range)?;
@@ -311,6 +501,44 @@ impl MerkleRadixTrie {
         }
     }
 
+    /// Returns the depth (counted in nodes below the root) at which `put_raw` would store a value
+    /// at the given key.
+    fn depth_after_put(
+        &self,
+        txn: &TransactionProxy,
+        key: &KeyNibbles,
+        missing_range: &Option<ops::RangeFrom<KeyNibbles>>,
+    ) -> usize {
+        let mut cur_node = self
+            .get_root(txn)
+            .expect("Merkle Radix Trie must have a root node!");
+        let mut depth = 0;
+
+        loop {
+            if !cur_node.key.is_prefix_of(key) {
+                // The value either becomes a hybrid node in place of the current node or a leaf
+                // below a new branch node that takes the place of the current node.
+                return if key.is_prefix_of(&cur_node.key) {
+                    depth
+                } else {
+                    depth + 1
+                };
+            }
+
+            if cur_node.key == *key {
+                return depth;
+            }
+
+            match cur_node.child_key(key, missing_range) {
+                Err(_) => return depth + 1,
+                Ok(child_key) => {
+                    cur_node = self.get_node(txn, &child_key).unwrap();
+                    depth += 1;
+                }
+            }
+        }
+    }
+
     /// Insert a value into the Merkle Radix Trie at the given key. If the key already exists then
     /// it will overwrite it. You can't use this function to check the existence of a given key.
     fn put_raw(
@@ -419,7 +647,7 @@ impl MerkleRadixTrie {
                 Ok(child_key) => {
                     root_path.push(cur_node);
 
-                    cur_node = txn.get_node(&self.db, &child_key).unwrap();
+                    cur_node = self.get_node(txn, &child_key).unwrap();
                 }
             }
         }
@@ -1186,6 +1414,7 @@ impl MerkleRadixTrie {
     }
 
     pub fn update_root(&self, txn: &mut WriteTransactionProxy) -> Result<(), MerkleRadixTrieError> {
+        self.io_counters.count_root_update();
         let missing_range = self.get_missing_range(txn);
         self.update_hashes(txn, &KeyNibbles::ROOT, &missing_range)
             .map_err(|_| MerkleRadixTrieError::IncompleteTrie)?;
@@ -1461,6 +1690,52 @@ impl MerkleRadixTrie {
         );
         TrieNodeIter::new(&self.db, txn, start_key, end_key.clone())
     }
+
+    /// Iterates over the raw values of all nodes whose key is exactly `key_len` nibbles long,
+    /// in ascending key order. Since keys are stored prefixed by their length, these nodes form
+    /// a contiguous range in the database and can be streamed without traversing the trie.
+    pub fn iter_values_with_len<'txn>(
+        &self,
+        txn: &'txn TransactionProxy,
+        key_len: usize,
+    ) -> TrieValueIter<'txn> {
+        TrieValueIter::new(&self.db, txn, key_len)
+    }
+}
+
+/// This iterator yields the keys and raw values of all nodes with a given key length.
+/// Nodes that don't hold a value are skipped.
+pub struct TrieValueIter<'txn> {
+    iter: IntoIterProxy<'txn, KeyNibbles, TrieNode>,
+    key_len: usize,
+}
+
+impl<'txn> TrieValueIter<'txn> {
+    fn new(db: &TableProxy, txn: &'txn TransactionProxy, key_len: usize) -> Self {
+        let start_key = KeyNibbles::from(&[0u8; KeyNibbles::MAX_BYTES][..]).slice(0, key_len);
+        let cursor = txn.cursor(db);
+
+        Self {
+            iter: cursor.into_iter_from(&start_key),
+            key_len,
+        }
+    }
+}
+
+impl<'txn> Iterator for TrieValueIter<'txn> {
+    type Item = (KeyNibbles, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, node) in self.iter.by_ref() {
+            if key.len() != self.key_len {
+                return None;
+            }
+            if let Some(value) = node.value {
+                return Some((key, value));
+            }
+        }
+        None
+    }
 }
 
 /// This iterator is meant to start at `start_key` and finish at `end_key`, both of these are inclusive.
@@ -2507,4 +2782,208 @@ mod tests {
         assert_eq!(original.count_nodes(&txn), (0, 2, 2));
         assert!(original.is_complete(&txn));
     }
+
+    #[test]
+    fn node_cache_serves_only_current_snapshot() {
+        let key: KeyNibbles = "413f22b3e".parse().unwrap();
+
+        let env = nimiq_database::volatile::VolatileDatabase::new(20).unwrap();
+        let trie = MerkleRadixTrie::new_with_node_cache(env.clone(), "database", 16);
+
+        let mut raw_txn = env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        trie.put(&mut txn, &key, 80085).expect("complete trie");
+        trie.update_root(&mut txn).expect("complete trie");
+        raw_txn.commit();
+
+        let cache = trie.node_cache().unwrap();
+        let old_txn = env.read_transaction();
+        assert_eq!(trie.get::<i32>(&old_txn, &key), Ok(Some(80085)));
+        assert_eq!(
+            trie.get::<i32>(&env.read_transaction(), &key),
+            Ok(Some(80085))
+        );
+        assert!(cache.hits() > 0);
+        assert!(!cache.is_empty());
+
+        let mut raw_txn = env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        trie.put(&mut txn, &key, 1337).expect("complete trie");
+        trie.update_root(&mut txn).expect("complete trie");
+        // Write transactions bypass the cache.
+        assert_eq!(trie.get::<i32>(&txn, &key), Ok(Some(1337)));
+        raw_txn.commit();
+
+        assert_eq!(
+            trie.get::<i32>(&env.read_transaction(), &key),
+            Ok(Some(1337))
+        );
+        // Read transactions on an outdated snapshot still see their own state.
+        assert_eq!(trie.get::<i32>(&old_txn, &key), Ok(Some(80085)));
+    }
+
+    #[test]
+    fn max_depth_rejects_deep_keys() {
+        let env = nimiq_database::volatile::VolatileDatabase::new(20).unwrap();
+        let trie = MerkleRadixTrie::new(env.clone(), "database").with_max_depth(3);
+        let mut raw_txn = env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+
+        // Every key is a prefix of the next one, so each key ends up one node deeper.
+        for key in ["4", "41", "413"] {
+            trie.put(&mut txn, &key.parse().unwrap(), 1)
+                .expect("complete trie");
+        }
+        assert_eq!(trie.count_nodes(&txn), (0, 2, 1));
+
+        assert_eq!(
+            trie.put(&mut txn, &"4130".parse().unwrap(), 1),
+            Err(MerkleRadixTrieError::TrieTooDeep(3))
+        );
+        // Keys further below the deepest key are rejected as well.
+        assert_eq!(
+            trie.put(&mut txn, &"4130f".parse().unwrap(), 1),
+            Err(MerkleRadixTrieError::TrieTooDeep(3))
+        );
+        assert_eq!(trie.count_nodes(&txn), (0, 2, 1));
+
+        // Updating existing keys and inserting shallow keys is still possible.
+        trie.put(&mut txn, &"413".parse().unwrap(), 2)
+            .expect("complete trie");
+        trie.put(&mut txn, &"5".parse().unwrap(), 1)
+            .expect("complete trie");
+        assert_eq!(trie.get(&txn, &"413".parse().unwrap()), Ok(Some(2)));
+    }
+
+    #[test]
+    fn get_with_depth_returns_the_node_depth() {
+        let env = nimiq_database::volatile::VolatileDatabase::new(20).unwrap();
+        let trie = MerkleRadixTrie::new(env.clone(), "database");
+        let mut raw_txn = env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+
+        for (i, key) in ["4", "41", "413", "5"].into_iter().enumerate() {
+            trie.put(&mut txn, &key.parse().unwrap(), i as i32)
+                .expect("complete trie");
+        }
+
+        assert_eq!(
+            trie.get_with_depth(&txn, &"4".parse().unwrap()),
+            Ok(Some((0, 1)))
+        );
+        assert_eq!(
+            trie.get_with_depth(&txn, &"413".parse().unwrap()),
+            Ok(Some((2, 3)))
+        );
+        assert_eq!(
+            trie.get_with_depth(&txn, &"5".parse().unwrap()),
+            Ok(Some((3, 1)))
+        );
+        assert_eq!(
+            trie.get_with_depth::<i32>(&txn, &"42".parse().unwrap()),
+            Ok(None)
+        );
+        assert_eq!(
+            trie.get_with_depth::<i32>(&txn, &"4130".parse().unwrap()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn get_many_is_aligned_with_the_keys() {
+        let env = nimiq_database::volatile::VolatileDatabase::new(20).unwrap();
+        let trie = MerkleRadixTrie::new(env.clone(), "database");
+        let mut raw_txn = env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+
+        for (i, key) in ["4", "413", "5"].into_iter().enumerate() {
+            trie.put(&mut txn, &key.parse().unwrap(), i as i32)
+                .expect("complete trie");
+        }
+
+        let keys: Vec<KeyNibbles> = ["5", "42", "4", "5", "413"]
+            .into_iter()
+            .map(|key| key.parse().unwrap())
+            .collect();
+        trie.reset_io_stats();
+        assert_eq!(
+            trie.get_many(&txn, &keys),
+            Ok(vec![Some(2), None, Some(0), Some(2), Some(1)])
+        );
+        // The root node and one node per distinct key.
+        assert_eq!(trie.io_stats().node_reads, 5);
+        assert_eq!(trie.get_many::<i32>(&txn, &[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn get_subtree_hash_commits_to_the_subtree() {
+        let env = nimiq_database::volatile::VolatileDatabase::new(20).unwrap();
+        let trie_1 = MerkleRadixTrie::new(env.clone(), "trie_1");
+        let trie_2 = MerkleRadixTrie::new(env.clone(), "trie_2");
+        let mut raw_txn = env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+
+        for key in ["4130", "4131", "5"] {
+            trie_1
+                .put(&mut txn, &key.parse().unwrap(), 1)
+                .expect("complete trie");
+        }
+        for key in ["4130", "4131", "6"] {
+            trie_2
+                .put(&mut txn, &key.parse().unwrap(), 1)
+                .expect("complete trie");
+        }
+        trie_1.update_root(&mut txn).expect("complete trie");
+        trie_2.update_root(&mut txn).expect("complete trie");
+
+        let subtree_hash = |trie: &MerkleRadixTrie, prefix: &str| {
+            trie.get_subtree_hash(&txn, &prefix.parse().unwrap())
+                .unwrap()
+        };
+
+        // The subtrees below "4" are the same, no matter which node the prefix ends in.
+        let hash = subtree_hash(&trie_1, "4");
+        assert!(hash.is_some());
+        assert_eq!(subtree_hash(&trie_2, "4"), hash);
+        assert_eq!(subtree_hash(&trie_1, "41"), hash);
+        assert_eq!(subtree_hash(&trie_1, "413"), hash);
+        assert_ne!(subtree_hash(&trie_1, "4130"), hash);
+
+        // The whole tries differ.
+        assert_ne!(subtree_hash(&trie_1, ""), subtree_hash(&trie_2, ""));
+        assert_eq!(
+            subtree_hash(&trie_1, ""),
+            Some(trie_1.root_hash_assert(&txn))
+        );
+
+        // There are no keys below these prefixes.
+        assert_eq!(subtree_hash(&trie_1, "6"), None);
+        assert_eq!(subtree_hash(&trie_1, "42"), None);
+    }
+
+    #[test]
+    fn io_stats_count_node_operations() {
+        let key: KeyNibbles = "413f22b3e".parse().unwrap();
+
+        let env = nimiq_database::volatile::VolatileDatabase::new(20).unwrap();
+        let trie = MerkleRadixTrie::new(env.clone(), "database");
+        trie.reset_io_stats();
+
+        let mut raw_txn = env.write_transaction();
+        let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+        trie.put(&mut txn, &key, 80085).expect("complete trie");
+        trie.update_root(&mut txn).expect("complete trie");
+
+        let stats = trie.io_stats();
+        assert!(stats.node_reads > 0);
+        assert!(stats.node_writes > 0);
+        assert_eq!(stats.node_removals, 0);
+        assert_eq!(stats.root_updates, 1);
+
+        trie.remove(&mut txn, &key);
+        assert!(trie.io_stats().node_removals > 0);
+
+        trie.reset_io_stats();
+        assert_eq!(trie.io_stats(), IoStats::default());
+    }
 }
@@ -0,0 +1,111 @@
+use std::time::{Duration, Instant};
+
+use nimiq_account::{Account, Accounts, BasicAccount, BlockLogger, BlockState};
+use nimiq_database::traits::{Database, WriteTransaction};
+use nimiq_keys::{Address, KeyPair as SchnorrKeyPair, SecureGenerate};
+use nimiq_primitives::{
+    coin::Coin, key_nibbles::KeyNibbles, policy::Policy, trie::trie_diff::ValueChange,
+};
+use nimiq_trie::WriteTransactionProxy;
+use rand::{CryptoRng, Rng};
+
+use crate::test_transaction::{generate_transactions, TestAccount, TestTransaction};
+
+/// The throughput measured by `bench_commit`.
+#[derive(Clone, Debug)]
+pub struct CommitBenchResult {
+    /// The number of transactions that were committed.
+    pub num_transactions: usize,
+    /// The time it took to commit the transactions to the accounts tree.
+    pub duration: Duration,
+    /// The number of committed transactions per second.
+    pub transactions_per_second: f64,
+    /// The total size of the values that were inserted or updated in the accounts tree.
+    pub bytes_written: usize,
+}
+
+/// Measures the throughput of `Accounts::commit`.
+///
+/// Funds `num_tx` freshly generated sender accounts, builds one signed basic transfer from each
+/// of them to a new recipient and times committing all transfers as a single block. The funded
+/// senders are kept in the accounts tree, while the transfers themselves are discarded afterwards
+/// so that the helper can be called repeatedly on the same `accounts`.
+pub fn bench_commit<R: Rng + CryptoRng>(
+    accounts: &Accounts,
+    num_tx: usize,
+    rng: &mut R,
+) -> CommitBenchResult {
+    let mut generate_account = || {
+        let keypair = SchnorrKeyPair::generate(rng);
+        TestAccount {
+            address: Address::from(&keypair.public),
+            keypair,
+        }
+    };
+
+    let mempool_transactions: Vec<TestTransaction> = (0..num_tx)
+        .map(|_| TestTransaction {
+            fee: 1,
+            value: 10,
+            sender: generate_account(),
+            recipient: generate_account(),
+        })
+        .collect();
+
+    // Fund the senders.
+    let mut raw_txn = accounts.env.write_transaction();
+    let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+    for mempool_transaction in &mempool_transactions {
+        let balance = Coin::from_u64_unchecked(mempool_transaction.fee + mempool_transaction.value);
+        accounts
+            .tree
+            .put(
+                &mut txn,
+                &KeyNibbles::from(&mempool_transaction.sender.address),
+                Account::Basic(BasicAccount { balance }),
+            )
+            .expect("Tree must be complete");
+    }
+    accounts
+        .tree
+        .update_root(&mut txn)
+        .expect("Tree must be complete");
+    raw_txn.commit();
+
+    let (transactions, _) = generate_transactions(mempool_transactions, true);
+    let block_state = BlockState::new(1 + Policy::genesis_block_number(), 1);
+
+    let mut raw_txn = accounts.env.write_transaction();
+    let mut txn: WriteTransactionProxy = (&mut raw_txn).into();
+    txn.start_recording();
+
+    let start = Instant::now();
+    accounts
+        .commit(
+            &mut txn,
+            &transactions,
+            &[],
+            &block_state,
+            &mut BlockLogger::empty(),
+        )
+        .expect("Failed to commit transactions");
+    let duration = start.elapsed();
+
+    let bytes_written = txn
+        .stop_recording()
+        .changes
+        .values()
+        .map(|change| match change {
+            ValueChange::Insert(new) | ValueChange::Update(_, new) => new.len(),
+            ValueChange::Delete(_) => 0,
+        })
+        .sum();
+    raw_txn.abort();
+
+    CommitBenchResult {
+        num_transactions: num_tx,
+        duration,
+        transactions_per_second: num_tx as f64 / duration.as_secs_f64(),
+        bytes_written,
+    }
+}
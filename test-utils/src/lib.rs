@@ -2,6 +2,7 @@ pub mod accounts_revert;
 pub mod block_production;
 pub mod blockchain;
 pub mod blockchain_with_rng;
+pub mod commit_bench;
 pub mod mock_node;
 pub mod node;
 pub mod test_custom_block;
@@ -87,6 +87,17 @@ impl MacroBlockCircuit {
         }
     }
 
+    /// Checks that this circuit continues the chain proven by a previous proof, i.e. that the
+    /// previous header hash of this circuit is the final header hash of the previous proof.
+    /// Proofs for circuits that are wired to the wrong commitment can't be chained, so this should
+    /// be checked before spending the effort of creating the proof.
+    pub fn ensure_follows(&self, prev_final_header_hash: &[u8; 32]) -> Result<(), SynthesisError> {
+        if self.prev_header_hash != *prev_final_header_hash {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        Ok(())
+    }
+
     pub fn rand<R: Rng + ?Sized>(rng: &mut R) -> Self {
         let proof = Proof {
             a: G1Affine::rand(rng),
@@ -255,3 +266,25 @@ impl ConstraintSynthesizer<MNT6Fq> for MacroBlockCircuit {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nimiq_test_log::test;
+    use nimiq_test_utils::test_rng::test_rng;
+
+    use super::*;
+
+    #[test]
+    fn ensure_follows_rejects_mismatched_chains() {
+        let mut rng = test_rng(false);
+        let circuit = MacroBlockCircuit::rand(&mut rng);
+
+        assert!(circuit.ensure_follows(&circuit.prev_header_hash).is_ok());
+
+        // Linking the circuit to its own final header hash is an off-by-one in the chain.
+        assert!(matches!(
+            circuit.ensure_follows(&circuit.final_header_hash),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
@@ -135,4 +135,9 @@ mod tests {
 
         assert_eq!(primitive_hash, gadget_hash.value().unwrap())
     }
+
+    #[test]
+    fn default_parameters_match_native() {
+        crate::setup::pedersen_parameters_self_test().unwrap();
+    }
 }
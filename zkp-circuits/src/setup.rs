@@ -6,13 +6,18 @@ use std::{
 use ark_crypto_primitives::snark::CircuitSpecificSetupSNARK;
 use ark_ec::pairing::Pairing;
 use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
-use ark_mnt4_753::MNT4_753;
-use ark_mnt6_753::MNT6_753;
+use ark_mnt4_753::{Fq as MNT4Fq, MNT4_753};
+use ark_mnt6_753::{Fq as MNT6Fq, MNT6_753};
+use ark_r1cs_std::{prelude::AllocVar, uint8::UInt8, R1CSVar};
+use ark_relations::r1cs::ConstraintSystem;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use nimiq_genesis::NetworkInfo;
 use nimiq_primitives::networks::NetworkId;
 use nimiq_serde::Deserialize;
-use nimiq_zkp_primitives::{NanoZKPError, VerifyingData};
+use nimiq_zkp_primitives::{
+    pedersen::{default_pedersen_hash, pedersen_parameters_mnt4},
+    pedersen_parameters_mnt6, NanoZKPError, VerifyingData,
+};
 use rand::{CryptoRng, Rng};
 
 use crate::{
@@ -25,6 +30,7 @@ use crate::{
         vk_commitments::VerifyingKeys,
         CircuitInput,
     },
+    gadgets::{mnt4, mnt6, pedersen::PedersenHashGadget, vk_commitment::VkCommitmentWindow},
     metadata::VerifyingKeyMetadata,
 };
 
@@ -47,6 +53,8 @@ pub fn setup<R: Rng + CryptoRng>(
         return Ok(());
     }
 
+    pedersen_parameters_self_test()?;
+
     setup_pk_tree_leaf(&mut rng, path, "pk_tree_5")?;
 
     setup_pk_tree_node_mnt4(&mut rng, path, "pk_tree_4", 4)?;
@@ -77,6 +85,44 @@ pub fn setup<R: Rng + CryptoRng>(
     Ok(())
 }
 
+/// Checks that the Pedersen parameters used in the circuits, i.e. the default parameters
+/// restricted to the `VkCommitmentWindow`, agree with the parameters used natively. To this end,
+/// the commitment to a fixed input is computed natively and in a minimal constraint system on
+/// both curves. A mismatch would otherwise only show up as proofs that fail to verify.
+pub fn pedersen_parameters_self_test() -> Result<(), NanoZKPError> {
+    let input: Vec<u8> = (0..=u8::MAX).collect();
+
+    // MNT6
+    let cs = ConstraintSystem::<MNT6Fq>::new_ref();
+    let generators_var = mnt6::DefaultPedersenParametersVar::new_constant(
+        cs.clone(),
+        pedersen_parameters_mnt6().sub_window::<VkCommitmentWindow>(),
+    )?;
+    let input_var = UInt8::new_witness_vec(cs.clone(), &input)?;
+    let hash_var =
+        PedersenHashGadget::<_, _, VkCommitmentWindow>::evaluate(&input_var, &generators_var)?;
+
+    if hash_var.value()? != default_pedersen_hash::<MNT6_753>(&input) || !cs.is_satisfied()? {
+        return Err(NanoZKPError::PedersenParametersMismatch);
+    }
+
+    // MNT4
+    let cs = ConstraintSystem::<MNT4Fq>::new_ref();
+    let generators_var = mnt4::DefaultPedersenParametersVar::new_constant(
+        cs.clone(),
+        pedersen_parameters_mnt4().sub_window::<VkCommitmentWindow>(),
+    )?;
+    let input_var = UInt8::new_witness_vec(cs.clone(), &input)?;
+    let hash_var =
+        PedersenHashGadget::<_, _, VkCommitmentWindow>::evaluate(&input_var, &generators_var)?;
+
+    if hash_var.value()? != default_pedersen_hash::<MNT4_753>(&input) || !cs.is_satisfied()? {
+        return Err(NanoZKPError::PedersenParametersMismatch);
+    }
+
+    Ok(())
+}
+
 pub fn load_verifying_data(path: &Path) -> Result<VerifyingData, NanoZKPError> {
     let metadata = fs::read(path.join("meta_data.bin"))?;
     let metadata = VerifyingKeyMetadata::deserialize_from_vec(&metadata)?;
@@ -2,8 +2,9 @@ use ark_groth16::Proof;
 use ark_mnt6_753::MNT6_753;
 use nimiq_block::MacroBlock;
 use nimiq_blockchain_interface::AbstractBlockchain;
-use nimiq_blockchain_proxy::BlockchainProxy;
+use nimiq_blockchain_proxy::{BlockchainProxy, BlockchainReadProxy};
 use nimiq_genesis::NetworkInfo;
+use nimiq_hash::{Blake2sHash, Hash};
 use nimiq_primitives::policy::Policy;
 use nimiq_zkp::{verify::verify, ZKP_VERIFYING_DATA};
 use nimiq_zkp_primitives::NanoZKPError;
@@ -63,6 +64,53 @@ pub(crate) fn get_proof_macro_blocks(
     Ok((new_block, genesis_block, proof))
 }
 
+/// Checks that the given macro block witness matches the election block of our blockchain at the
+/// same height. The block number, header hash, pk tree root and signer bitmap must all be equal.
+/// This is meant to be called before proving, since proving against a stale or wrong block is
+/// expensive and would only be noticed once the proof fails to verify.
+pub fn validate_macro_block_witness(
+    blockchain: &BlockchainReadProxy,
+    block: &MacroBlock,
+) -> Result<(), Error> {
+    let chain_block = blockchain
+        .get_block_at(block.block_number(), true)
+        .map_err(|_| Error::InvalidBlock)?;
+
+    if !chain_block.is_election() {
+        return Err(Error::InvalidBlock);
+    }
+    let chain_block = chain_block.unwrap_macro();
+
+    if chain_block.block_number() != block.block_number() {
+        return Err(Error::WitnessMismatch("block number"));
+    }
+
+    if chain_block.hash_blake2s() != block.hash_blake2s() {
+        return Err(Error::WitnessMismatch("header hash"));
+    }
+
+    let pk_tree_root = |block: &MacroBlock| {
+        block
+            .get_validators()
+            .map(|validators| validators.hash::<Blake2sHash>())
+    };
+    if pk_tree_root(&chain_block) != pk_tree_root(block) {
+        return Err(Error::WitnessMismatch("pk tree root"));
+    }
+
+    let signers = |block: &MacroBlock| {
+        block
+            .justification
+            .as_ref()
+            .map(|justification| justification.sig.signers.clone())
+    };
+    if signers(&chain_block) != signers(block) {
+        return Err(Error::WitnessMismatch("signer bitmap"));
+    }
+
+    Ok(())
+}
+
 /// Validates proof and returns the new zkp state. Assumes the blocks provided are valid.
 pub(crate) fn validate_proof_get_new_state(
     proof: Proof<MNT6_753>,
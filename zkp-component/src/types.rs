@@ -177,6 +177,9 @@ pub enum Error {
     #[error("Invalid proof")]
     InvalidProof,
 
+    #[error("Macro block witness does not match the blockchain: {0}")]
+    WitnessMismatch(&'static str),
+
     #[error("Request Error: {0}")]
     Request(#[from] RequestError),
 }
@@ -2,6 +2,7 @@ use std::{path::Path, sync::Arc};
 
 use ark_groth16::Proof;
 use nimiq_blockchain::{BlockProducer, Blockchain, BlockchainConfig};
+use nimiq_blockchain_interface::AbstractBlockchain;
 use nimiq_blockchain_proxy::BlockchainProxy;
 use nimiq_database::volatile::VolatileDatabase;
 use nimiq_primitives::{networks::NetworkId, policy::Policy};
@@ -15,8 +16,8 @@ use nimiq_utils::time::OffsetTime;
 use nimiq_zkp::ZKP_VERIFYING_DATA;
 use nimiq_zkp_component::{
     proof_store::{DBProofStore, ProofStore},
-    proof_utils::validate_proof,
-    types::ZKProof,
+    proof_utils::{validate_macro_block_witness, validate_proof},
+    types::{Error, ZKProof},
 };
 use parking_lot::RwLock;
 
@@ -178,3 +179,47 @@ async fn can_store_and_load_zkp_state_from_db() {
         "Load from db was not successful"
     );
 }
+
+#[test(tokio::test)]
+async fn can_validate_macro_block_witness() {
+    let blockchain = blockchain();
+
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks_with_rng(
+        &producer,
+        &blockchain,
+        Policy::batches_per_epoch() as usize,
+        &mut get_base_seed(),
+    );
+
+    let block = blockchain.read().state.election_head.clone();
+    let blockchain = BlockchainProxy::from(blockchain);
+    let blockchain = blockchain.read();
+
+    // The election block of the chain is a valid witness.
+    assert!(validate_macro_block_witness(&blockchain, &block).is_ok());
+
+    // A witness at a height without an election block is rejected.
+    let mut wrong_block = block.clone();
+    wrong_block.header.block_number -= 1;
+    assert!(matches!(
+        validate_macro_block_witness(&blockchain, &wrong_block),
+        Err(Error::InvalidBlock)
+    ));
+
+    // A witness with a different header is rejected.
+    let mut wrong_block = block.clone();
+    wrong_block.header.timestamp += 1;
+    assert!(matches!(
+        validate_macro_block_witness(&blockchain, &wrong_block),
+        Err(Error::WitnessMismatch("header hash"))
+    ));
+
+    // A witness with different signers is rejected.
+    let mut wrong_block = block;
+    wrong_block.justification.as_mut().unwrap().sig.signers = Default::default();
+    assert!(matches!(
+        validate_macro_block_witness(&blockchain, &wrong_block),
+        Err(Error::WitnessMismatch("signer bitmap"))
+    ));
+}
@@ -5,11 +5,13 @@
 
 pub use pedersen::pedersen_parameters_mnt6;
 pub use serialize::*;
+pub use state_commitment::*;
 pub use vk_commitment::*;
 
 pub mod ext_traits;
 pub mod pedersen;
 mod serialize;
+mod state_commitment;
 mod vk_commitment;
 
 use std::io;
@@ -32,4 +34,6 @@ pub enum NanoZKPError {
     EmptyProof,
     #[error("invalid block")]
     InvalidBlock,
+    #[error("pedersen parameters of the circuits don't match the native ones")]
+    PedersenParametersMismatch,
 }
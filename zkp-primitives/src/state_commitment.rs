@@ -0,0 +1,16 @@
+use ark_mnt6_753::MNT6_753;
+use nimiq_hash::{Blake2bHash, HashOutput};
+
+use crate::pedersen::{default_pedersen_hash, DefaultPedersenParameters95};
+
+/// Calculates a commitment to the accounts state off-circuit, using the same Pedersen parameters
+/// as the MNT6-753 circuits. The state is represented by the root hash of the accounts tree, which
+/// already commits to every account, so the commitment binds the whole state while its input
+/// stays within the fixed size the Pedersen parameters can hash.
+pub fn state_commitment(accounts_root: &Blake2bHash) -> [u8; 95] {
+    // Calculate the Pedersen hash.
+    let hash = default_pedersen_hash::<MNT6_753>(accounts_root.as_bytes());
+
+    // Serialize the Pedersen commitment.
+    MNT6_753::g1_to_bytes(&hash)
+}
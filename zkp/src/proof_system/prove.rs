@@ -720,6 +720,9 @@ fn prove_macro_block<R: CryptoRng + Rng>(
         agg_pk_chunks.push(agg_pk);
     }
 
+    let prev_block_number = prev_block.block_number();
+    let final_block_number = final_block.block_number();
+
     // Create the circuit.
     let circuit = MacroBlockCircuit::new(
         keys.clone(),
@@ -734,6 +737,18 @@ fn prove_macro_block<R: CryptoRng + Rng>(
     let prev_header_hash = circuit.prev_header_hash;
     let final_header_hash = circuit.final_header_hash;
 
+    // Record the inputs of the circuit, so that a proof that fails to verify can be traced back
+    // to the blocks and public key tree it was created from.
+    log::debug!(
+        prev_block_number,
+        final_block_number,
+        prev_header_hash = %Blake2sHash::from(prev_header_hash),
+        final_header_hash = %Blake2sHash::from(final_header_hash),
+        l_pk_node_hash = %Blake2sHash::from(l_pk_node_hash),
+        r_pk_node_hash = %Blake2sHash::from(r_pk_node_hash),
+        "Proving macro block circuit"
+    );
+
     // Create the proof.
     let proof = Groth16::<MNT4_753>::prove(&proving_key, circuit, rng)?;
 
@@ -1,9 +1,12 @@
 use ark_crypto_primitives::snark::SNARK;
+use ark_ec::pairing::Pairing;
 use ark_ff::ToConstraintField;
-use ark_groth16::{Groth16, Proof};
+use ark_groth16::{Groth16, Proof, VerifyingKey};
 use ark_mnt6_753::MNT6_753;
 use nimiq_hash::Blake2sHash;
 use nimiq_zkp_primitives::{NanoZKPError, VerifyingData};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 /// This function verifies a proof for the Merger Wrapper circuit, which implicitly is a proof for
 /// the entire light macro sync. It is very fast, shouldn't take more than a second, even on older
@@ -30,3 +33,38 @@ pub fn verify(
     // Return result.
     Ok(result)
 }
+
+/// This function verifies a batch of PKTree proofs that share the same verifying key, i.e. proofs
+/// for circuits at the same level of the PKTree, and returns the indices of the proofs that are
+/// invalid. Each proof is given together with its prepared public inputs. With the `parallel`
+/// feature, the proofs are verified concurrently.
+/// This allows rejecting bad sub-proofs before starting the expensive synthesis of the recursive
+/// proofs that build on them.
+pub fn batch_verify_pk_tree_proofs<E: Pairing>(
+    // The verifying key of the PKTree circuit at the level of the proofs.
+    verifying_key: &VerifyingKey<E>,
+    // The SNARK proofs together with their public inputs.
+    proofs: &[(Proof<E>, Vec<E::ScalarField>)],
+) -> Result<Vec<usize>, NanoZKPError> {
+    let prepared_vk = Groth16::<E>::process_vk(verifying_key)?;
+
+    #[cfg(not(feature = "parallel"))]
+    let iter = proofs.iter().enumerate();
+    #[cfg(feature = "parallel")]
+    let iter = proofs.par_iter().enumerate();
+
+    // Verify proofs.
+    let results = iter
+        .map(|(i, (proof, inputs))| {
+            Groth16::<E>::verify_with_processed_vk(&prepared_vk, inputs, proof)
+                .map(|valid| (i, valid))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Return the indices of the invalid proofs.
+    Ok(results
+        .into_iter()
+        .filter(|(_, valid)| !valid)
+        .map(|(i, _)| i)
+        .collect())
+}
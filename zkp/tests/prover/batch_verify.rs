@@ -0,0 +1,75 @@
+use ark_crypto_primitives::snark::{CircuitSpecificSetupSNARK, SNARK};
+use ark_groth16::Groth16;
+use ark_mnt6_753::{Fr, MNT6_753};
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
+};
+use nimiq_test_log::test;
+use nimiq_test_utils::test_rng::test_rng;
+use nimiq_zkp::verify::batch_verify_pk_tree_proofs;
+
+#[derive(Clone)]
+pub struct SquareCircuit {
+    // Witnesses (private)
+    root: Fr,
+    // Inputs (public)
+    square: Fr,
+}
+
+impl ConstraintSynthesizer<Fr> for SquareCircuit {
+    /// This function generates the constraints for the circuit.
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let root_var = cs.new_witness_variable(|| Ok(self.root))?;
+
+        let square_var = cs.new_input_variable(|| Ok(self.square))?;
+
+        cs.enforce_constraint(lc!() + root_var, lc!() + root_var, lc!() + square_var)?;
+
+        Ok(())
+    }
+}
+
+// This test takes a long time to finish, so run it only when necessary.
+#[test]
+#[cfg_attr(not(feature = "expensive-tests"), ignore)]
+fn batch_verify_reports_invalid_proofs() {
+    // Create random number generator.
+    let rng = &mut test_rng(false);
+
+    let circuit = |root: u64| SquareCircuit {
+        root: Fr::from(root),
+        square: Fr::from(root * root),
+    };
+
+    // Create the proving and verifying keys.
+    let (pk, vk) = Groth16::<MNT6_753>::setup(circuit(1), rng).unwrap();
+
+    // Create the proofs.
+    let proof_2 = Groth16::<MNT6_753>::prove(&pk, circuit(2), rng).unwrap();
+    let proof_3 = Groth16::<MNT6_753>::prove(&pk, circuit(3), rng).unwrap();
+    let proof_4 = Groth16::<MNT6_753>::prove(&pk, circuit(4), rng).unwrap();
+
+    // Tamper with a proof by mixing it with another one.
+    let mut tampered_proof = proof_4.clone();
+    tampered_proof.a = proof_2.a;
+
+    let proofs = vec![
+        (proof_2, vec![Fr::from(4u64)]),
+        (proof_3.clone(), vec![Fr::from(9u64)]),
+        // A valid proof with wrong inputs.
+        (proof_3, vec![Fr::from(10u64)]),
+        (tampered_proof, vec![Fr::from(16u64)]),
+        (proof_4, vec![Fr::from(16u64)]),
+    ];
+
+    assert_eq!(
+        batch_verify_pk_tree_proofs(&vk, &proofs).unwrap(),
+        vec![2, 3]
+    );
+
+    // Only valid proofs result in no invalid indices.
+    assert!(batch_verify_pk_tree_proofs(&vk, &proofs[..2])
+        .unwrap()
+        .is_empty());
+}
@@ -1 +1,2 @@
+mod batch_verify;
 mod recursive_input;